@@ -0,0 +1,306 @@
+mod sink;
+
+use crate::constants::{
+    ALERT_HYSTERESIS_FACTOR, CRITICAL_ALERT_FACTOR, EMPTY_ALERT_AMOUNT, RESOURCE_ALERT_FACTOR,
+};
+use crate::container::ContainerKind;
+use serde::Serialize;
+pub use sink::{AlertNotifier, AlertSinkSpec};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// How serious a low level alert is. Each severity is more serious than the previous one.
+pub enum Severity {
+    Warning = 0,
+    Critical,
+    Empty,
+}
+
+impl Severity {
+    /// Every severity, from the least to the most serious one.
+    pub const ALL: [Severity; 3] = [Severity::Warning, Severity::Critical, Severity::Empty];
+
+    /// Returns the name used to refer to the severity in the command line and in the alert sinks.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+            Severity::Empty => "empty",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Severity::ALL
+            .into_iter()
+            .find(|severity| severity.id() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Severidad desconocida: {} (opciones: warning, critical, empty)",
+                    s
+                )
+            })
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Severity::Warning => "ADVERTENCIA",
+            Severity::Critical => "CRÍTICO",
+            Severity::Empty => "VACÍO",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Level under which an alert is raised, as an absolute amount or as a fraction of the
+/// capacity of the container.
+pub enum Threshold {
+    Absolute(u64),
+    Fraction(f64),
+}
+
+impl Threshold {
+    /// Returns the threshold as an amount, for a container with the given capacity.
+    pub fn resolve(&self, capacity: u64) -> u64 {
+        match self {
+            Threshold::Absolute(amount) => *amount,
+            Threshold::Fraction(fraction) => (capacity as f64 * fraction) as u64,
+        }
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    /// Values with a decimal point are fractions of the capacity (e.g. `0.25`),
+    /// the rest are absolute amounts (e.g. `500`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Threshold inválido: {}", s);
+        if s.contains('.') {
+            let fraction: f64 = s.parse().map_err(|_| invalid())?;
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(invalid());
+            }
+            Ok(Threshold::Fraction(fraction))
+        } else {
+            Ok(Threshold::Absolute(s.parse().map_err(|_| invalid())?))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Thresholds of each severity for a container, as configured.
+pub struct AlertThresholds {
+    thresholds: [Threshold; 3],
+}
+
+impl AlertThresholds {
+    /// Replaces the threshold of the given severity.
+    pub fn set(&mut self, severity: Severity, threshold: Threshold) {
+        self.thresholds[severity as usize] = threshold;
+    }
+
+    /// Returns the thresholds as amounts, for a container with the given capacity.
+    pub fn resolve(&self, capacity: u64) -> AlertLevels {
+        AlertLevels {
+            thresholds: self.thresholds.map(|threshold| threshold.resolve(capacity)),
+            hysteresis: (capacity as f64 * ALERT_HYSTERESIS_FACTOR) as u64,
+        }
+    }
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            thresholds: [
+                Threshold::Fraction(RESOURCE_ALERT_FACTOR),
+                Threshold::Fraction(CRITICAL_ALERT_FACTOR),
+                Threshold::Absolute(EMPTY_ALERT_AMOUNT),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Thresholds of each severity for a container, as amounts.
+pub struct AlertLevels {
+    thresholds: [u64; 3],
+    hysteresis: u64,
+}
+
+impl AlertLevels {
+    /// Returns the amount under which the given severity is raised.
+    pub fn get_threshold(&self, severity: Severity) -> u64 {
+        self.thresholds[severity as usize]
+    }
+
+    /// Returns the most serious severity for the given amount, if any.
+    /// A severity that is already raised is kept until the amount goes `hysteresis` above
+    /// its threshold, so a level oscillating around a threshold does not raise it repeatedly.
+    pub fn severity(&self, amount: u64, current: Option<Severity>) -> Option<Severity> {
+        Severity::ALL.iter().rev().copied().find(|severity| {
+            let threshold = self.get_threshold(*severity);
+            let raised = current.is_some_and(|current| current >= *severity);
+            amount < threshold || (raised && amount < threshold + self.hysteresis)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// What happened to the low level alert of a container.
+pub enum AlertEvent {
+    /// The level went under the threshold of a severity
+    Raised,
+    /// The alert is still not cleared
+    Reminder,
+    /// The level went up, but not enough to clear the alert
+    Lowered,
+    /// The level is back above every threshold
+    Cleared,
+    /// The container is expected to run out within the forecast horizon
+    Forecast,
+    /// The container is no longer expected to run out within the forecast horizon
+    ForecastCleared,
+}
+
+#[derive(Clone, Debug, Serialize)]
+/// Alert about the level of a container, as sent to the alert sinks.
+pub struct Alert {
+    container: ContainerKind,
+    event: AlertEvent,
+    severity: Option<Severity>,
+    amount: u64,
+    /// Milliseconds since the Unix epoch
+    timestamp: u128,
+    message: String,
+}
+
+impl Alert {
+    /// Creates an alert that happens now.
+    pub fn new(
+        container: ContainerKind,
+        event: AlertEvent,
+        severity: Option<Severity>,
+        amount: u64,
+        message: String,
+    ) -> Self {
+        Self {
+            container,
+            event,
+            severity,
+            amount,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis()),
+            message,
+        }
+    }
+
+    /// Returns the container the alert is about.
+    pub fn get_container(&self) -> ContainerKind {
+        self.container
+    }
+
+    /// Returns what happened to the alert.
+    pub fn get_event(&self) -> AlertEvent {
+        self.event
+    }
+
+    /// Returns the severity of the alert, if it is not cleared.
+    pub fn get_severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    /// Returns the level of the container when the alert happened.
+    pub fn get_amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Returns the human readable description of the alert.
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the alert as a single line of JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize alert")
+    }
+}
+
+impl Display for AlertEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AlertEvent::Raised => "raised",
+            AlertEvent::Reminder => "reminder",
+            AlertEvent::Lowered => "lowered",
+            AlertEvent::Cleared => "cleared",
+            AlertEvent::Forecast => "forecast",
+            AlertEvent::ForecastCleared => "forecast_cleared",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Alert levels of a container of 1000, with a hysteresis of 100.
+    fn levels() -> AlertLevels {
+        let mut thresholds = AlertThresholds::default();
+        thresholds.set(Severity::Warning, Threshold::Absolute(300));
+        thresholds.set(Severity::Critical, Threshold::Absolute(100));
+        thresholds.set(Severity::Empty, Threshold::Absolute(1));
+        thresholds.resolve(1000)
+    }
+
+    #[test]
+    fn the_most_serious_severity_under_its_threshold_is_raised() {
+        let levels = levels();
+
+        assert_eq!(levels.severity(500, None), None);
+        assert_eq!(levels.severity(299, None), Some(Severity::Warning));
+        assert_eq!(levels.severity(50, None), Some(Severity::Critical));
+        assert_eq!(levels.severity(0, None), Some(Severity::Empty));
+    }
+
+    #[test]
+    fn a_raised_severity_is_only_cleared_once_the_level_goes_above_the_hysteresis() {
+        let levels = levels();
+
+        assert_eq!(
+            levels.severity(350, Some(Severity::Warning)),
+            Some(Severity::Warning)
+        );
+        assert_eq!(levels.severity(350, None), None);
+        assert_eq!(levels.severity(400, Some(Severity::Warning)), None);
+        assert_eq!(
+            levels.severity(150, Some(Severity::Critical)),
+            Some(Severity::Critical)
+        );
+        assert_eq!(
+            levels.severity(150, Some(Severity::Warning)),
+            Some(Severity::Warning)
+        );
+    }
+
+    #[test]
+    fn thresholds_with_a_decimal_point_are_fractions_of_the_capacity() {
+        let fraction: Threshold = "0.25".parse().unwrap();
+        let absolute: Threshold = "25".parse().unwrap();
+
+        assert_eq!(fraction.resolve(1000), 250);
+        assert_eq!(absolute.resolve(1000), 25);
+        assert!("1.5".parse::<Threshold>().is_err());
+    }
+}
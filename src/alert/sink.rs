@@ -0,0 +1,449 @@
+use crate::alert::Alert;
+use crate::constants::{
+    ALERT_RATE_LIMIT_WINDOW, ALERT_SINK_MAX_RETRIES, ALERT_SINK_RETRY_DELAY, ALERT_WEBHOOK_TIMEOUT,
+    COLOR_RED, COLOR_RESET,
+};
+use crate::stats::SinkStats;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Destination where alerts are delivered, besides the console.
+pub trait AlertSink: Send + Sync {
+    /// Delivers the alert. Returns an error describing what went wrong if it could not.
+    fn send(&self, alert: &Alert) -> Result<(), String>;
+
+    /// Returns a description of the sink, used in the reports.
+    fn describe(&self) -> String;
+}
+
+/// Appends every alert, as a line of JSON, to a file.
+pub struct FileSink {
+    path: PathBuf,
+}
+
+impl FileSink {
+    /// Creates a sink that appends to the given file, creating it if needed.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl AlertSink for FileSink {
+    fn send(&self, alert: &Alert) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|error| error.to_string())?;
+        writeln!(file, "{}", alert.to_json()).map_err(|error| error.to_string())
+    }
+
+    fn describe(&self) -> String {
+        format!("archivo {}", self.path.display())
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Address of an HTTP endpoint, taken from a `http://<host>[:<port>][/<path>]` URL.
+pub struct HttpEndpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl FromStr for HttpEndpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("URL inválida: {} (solo se soportan URLs http://)", s);
+        let rest = s.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.split_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+impl Display for HttpEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+/// POSTs every alert as JSON to an HTTP endpoint.
+pub struct WebhookSink {
+    endpoint: HttpEndpoint,
+}
+
+impl WebhookSink {
+    /// Creates a sink that posts to the given endpoint.
+    pub fn new(endpoint: HttpEndpoint) -> Self {
+        Self { endpoint }
+    }
+
+    /// Opens a connection to the endpoint, with timeouts so a stuck server does not block
+    /// the delivery of the next alerts.
+    fn connect(&self) -> Result<TcpStream, String> {
+        let timeout = Duration::from_millis(ALERT_WEBHOOK_TIMEOUT);
+        let address = (self.endpoint.host.as_str(), self.endpoint.port)
+            .to_socket_addrs()
+            .map_err(|error| error.to_string())?
+            .next()
+            .ok_or_else(|| format!("No se pudo resolver {}", self.endpoint.host))?;
+        let stream =
+            TcpStream::connect_timeout(&address, timeout).map_err(|error| error.to_string())?;
+        stream
+            .set_read_timeout(Some(timeout))
+            .and_then(|_| stream.set_write_timeout(Some(timeout)))
+            .map_err(|error| error.to_string())?;
+        Ok(stream)
+    }
+}
+
+impl AlertSink for WebhookSink {
+    fn send(&self, alert: &Alert) -> Result<(), String> {
+        let body = alert.to_json();
+        let mut stream = self.connect()?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.endpoint.path,
+            self.endpoint.host,
+            body.len(),
+            body
+        )
+        .map_err(|error| error.to_string())?;
+
+        let mut status_line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut status_line)
+            .map_err(|error| error.to_string())?;
+        let status = status_line.split_whitespace().nth(1).unwrap_or_default();
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(format!("Respuesta inesperada: {}", status_line.trim()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("webhook {}", self.endpoint)
+    }
+}
+
+/// Runs a shell command for every alert. The alert is available to the command through the
+/// `ALERT_CONTAINER`, `ALERT_EVENT`, `ALERT_SEVERITY`, `ALERT_AMOUNT`, `ALERT_MESSAGE` and
+/// `ALERT_JSON` environment variables.
+pub struct CommandSink {
+    command: String,
+}
+
+impl CommandSink {
+    /// Creates a sink that runs the given command with `sh -c`.
+    pub fn new(command: String) -> Self {
+        Self { command }
+    }
+}
+
+impl AlertSink for CommandSink {
+    fn send(&self, alert: &Alert) -> Result<(), String> {
+        let severity = alert.get_severity().map_or("", |severity| severity.id());
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .env("ALERT_CONTAINER", alert.get_container().id())
+            .env("ALERT_EVENT", alert.get_event().to_string())
+            .env("ALERT_SEVERITY", severity)
+            .env("ALERT_AMOUNT", alert.get_amount().to_string())
+            .env("ALERT_MESSAGE", alert.get_message())
+            .env("ALERT_JSON", alert.to_json())
+            .status()
+            .map_err(|error| error.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("El comando terminó con {}", status))
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("comando `{}`", self.command)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Alert sinks selectable from the command line.
+pub enum AlertSinkSpec {
+    File(PathBuf),
+    Webhook(HttpEndpoint),
+    Command(String),
+}
+
+impl AlertSinkSpec {
+    /// Creates the sink represented by this spec.
+    pub fn build(&self) -> Box<dyn AlertSink> {
+        match self {
+            AlertSinkSpec::File(path) => Box::new(FileSink::new(path.clone())),
+            AlertSinkSpec::Webhook(endpoint) => Box::new(WebhookSink::new(endpoint.clone())),
+            AlertSinkSpec::Command(command) => Box::new(CommandSink::new(command.clone())),
+        }
+    }
+}
+
+/// A sink with its own queue of alerts, delivered from its own thread.
+struct SinkQueue {
+    sink: Box<dyn AlertSink>,
+    sender: Mutex<Option<Sender<Alert>>>,
+    receiver: Mutex<Receiver<Alert>>,
+    stats: Mutex<SinkStats>,
+}
+
+/// Delivers the alerts to every sink from a thread of its own, so a slow or failing sink
+/// neither delays the threads that raise the alerts nor the deliveries to the other sinks.
+///
+/// Each delivery is retried with an increasing delay, and at most `rate_limit` alerts are
+/// delivered to each sink every `ALERT_RATE_LIMIT_WINDOW` seconds; the rest are dropped. Only
+/// the deliveries that succeed count against the limit.
+pub struct AlertNotifier {
+    sinks: Vec<SinkQueue>,
+    rate_limit: Option<usize>,
+}
+
+impl AlertNotifier {
+    /// Creates a notifier for the given sinks.
+    pub fn new(sinks: Vec<Box<dyn AlertSink>>, rate_limit: Option<usize>) -> Self {
+        Self {
+            sinks: sinks
+                .into_iter()
+                .map(|sink| {
+                    let (sender, receiver) = mpsc::channel();
+                    SinkQueue {
+                        sink,
+                        sender: Mutex::new(Some(sender)),
+                        receiver: Mutex::new(receiver),
+                        stats: Mutex::new(SinkStats::new()),
+                    }
+                })
+                .collect(),
+            rate_limit,
+        }
+    }
+
+    /// Queues the alert to be delivered to every sink.
+    pub fn notify(&self, alert: Alert) {
+        for sink in &self.sinks {
+            if let Some(sender) = sink.sender.lock().expect("Failed to lock sender").as_ref() {
+                // Si el thread de entrega ya terminó, la alerta solo se muestra por consola.
+                let _ = sender.send(alert.clone());
+            }
+        }
+    }
+
+    /// Stops accepting alerts. The delivery threads end once the queued ones are delivered.
+    pub fn close(&self) {
+        for sink in &self.sinks {
+            sink.sender.lock().expect("Failed to lock sender").take();
+        }
+    }
+
+    /// Delivers the queued alerts until the notifier is closed, with a thread for each sink.
+    pub fn deliver(&self) {
+        thread::scope(|scope| {
+            for sink in &self.sinks {
+                scope.spawn(move || self.deliver_queue(sink));
+            }
+        });
+    }
+
+    /// Delivers the alerts queued for a single sink, until the notifier is closed.
+    fn deliver_queue(&self, sink: &SinkQueue) {
+        let receiver = sink.receiver.lock().expect("Failed to lock receiver");
+        // Momentos de las últimas entregas exitosas, para el límite de alertas.
+        let mut sent = VecDeque::new();
+        while let Ok(alert) = receiver.recv() {
+            self.deliver_to(sink, &mut sent, &alert);
+        }
+    }
+
+    /// Delivers the alert to a single sink, honoring the rate limit and retrying on failure.
+    fn deliver_to(&self, sink: &SinkQueue, sent: &mut VecDeque<Instant>, alert: &Alert) {
+        let window = Duration::from_secs(ALERT_RATE_LIMIT_WINDOW);
+        while sent.front().is_some_and(|last| last.elapsed() >= window) {
+            sent.pop_front();
+        }
+        let stats = || sink.stats.lock().expect("Failed to lock sink stats");
+        if self.rate_limit.is_some_and(|limit| sent.len() >= limit) {
+            stats().record_rate_limited();
+            return;
+        }
+
+        let mut delay = Duration::from_millis(ALERT_SINK_RETRY_DELAY);
+        for attempt in 0..=ALERT_SINK_MAX_RETRIES {
+            if attempt > 0 {
+                thread::sleep(delay);
+                delay *= 2;
+                stats().record_retry();
+            }
+            match sink.sink.send(alert) {
+                Ok(()) => {
+                    sent.push_back(Instant::now());
+                    stats().record_delivered();
+                    return;
+                }
+                Err(error) => println!(
+                    "{}[Notificación de alertas]{} - Falló el envío a {}: {}",
+                    COLOR_RED,
+                    COLOR_RESET,
+                    sink.sink.describe(),
+                    error
+                ),
+            }
+        }
+        stats().record_failed();
+    }
+
+    /// Returns the delivery stats of each sink, along with its description.
+    pub fn stats(&self) -> Vec<(String, SinkStats)> {
+        self.sinks
+            .iter()
+            .map(|sink| {
+                let stats = *sink.stats.lock().expect("Failed to lock sink stats");
+                (sink.sink.describe(), stats)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::AlertEvent;
+    use crate::container::ContainerKind;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn alert() -> Alert {
+        Alert::new(
+            ContainerKind::ColdMilk,
+            AlertEvent::Raised,
+            None,
+            0,
+            String::new(),
+        )
+    }
+
+    /// Sink that takes a while to deliver each alert.
+    struct SlowSink(Duration);
+
+    impl AlertSink for SlowSink {
+        fn send(&self, _alert: &Alert) -> Result<(), String> {
+            thread::sleep(self.0);
+            Ok(())
+        }
+
+        fn describe(&self) -> String {
+            String::from("lento")
+        }
+    }
+
+    /// Sink that fails the given number of attempts before delivering, recording when it did.
+    struct FlakySink {
+        failures: u32,
+        attempts: AtomicU32,
+        delivered_at: Arc<Mutex<Vec<Instant>>>,
+    }
+
+    impl FlakySink {
+        fn new(failures: u32, delivered_at: Arc<Mutex<Vec<Instant>>>) -> Self {
+            Self {
+                failures,
+                attempts: AtomicU32::new(0),
+                delivered_at,
+            }
+        }
+    }
+
+    impl AlertSink for FlakySink {
+        fn send(&self, _alert: &Alert) -> Result<(), String> {
+            if self.attempts.fetch_add(1, Ordering::Relaxed) < self.failures {
+                return Err(String::from("caído"));
+            }
+            self.delivered_at.lock().unwrap().push(Instant::now());
+            Ok(())
+        }
+
+        fn describe(&self) -> String {
+            String::from("intermitente")
+        }
+    }
+
+    #[test]
+    fn a_slow_sink_does_not_delay_the_deliveries_to_the_others() {
+        let delivered_at = Arc::new(Mutex::new(Vec::new()));
+        let notifier = AlertNotifier::new(
+            vec![
+                Box::new(SlowSink(Duration::from_secs(1))),
+                Box::new(FlakySink::new(0, delivered_at.clone())),
+            ],
+            None,
+        );
+        let start = Instant::now();
+        notifier.notify(alert());
+        notifier.notify(alert());
+        notifier.close();
+
+        notifier.deliver();
+
+        let delivered_at = delivered_at.lock().unwrap();
+        assert_eq!(delivered_at.len(), 2);
+        assert!(delivered_at
+            .iter()
+            .all(|at| at.duration_since(start) < Duration::from_millis(500)));
+        assert!(start.elapsed() >= Duration::from_secs(2));
+    }
+
+    #[test]
+    fn failed_deliveries_do_not_count_against_the_rate_limit() {
+        let notifier = AlertNotifier::new(
+            vec![Box::new(FlakySink::new(
+                ALERT_SINK_MAX_RETRIES + 1,
+                Arc::new(Mutex::new(Vec::new())),
+            ))],
+            Some(1),
+        );
+        notifier.notify(alert());
+        notifier.notify(alert());
+        notifier.notify(alert());
+        notifier.close();
+
+        notifier.deliver();
+
+        let (_, stats) = &notifier.stats()[0];
+        assert_eq!(
+            stats.to_string(),
+            "Entregadas: 1 - Reintentos: 3 - Fallidas: 1 - Descartadas por límite: 1"
+        );
+    }
+}
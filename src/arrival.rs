@@ -0,0 +1,253 @@
+use crate::constants::ARRIVAL_CHECK_INTERVAL;
+use crate::order::Order;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq)]
+/// Rate of arrivals (orders per second) that changes over time, as a list of the moment each
+/// rate starts, since the machine started, sorted from the first one.
+pub struct RateProfile(Vec<(Duration, f64)>);
+
+impl RateProfile {
+    /// Returns the rate at the given moment, and when the next rate starts, if there is one.
+    fn rate_at(&self, elapsed: Duration) -> (f64, Option<Duration>) {
+        let index = self
+            .0
+            .iter()
+            .rposition(|(start, _)| *start <= elapsed)
+            .unwrap_or(0);
+        (
+            self.0[index].1,
+            self.0.get(index + 1).map(|(start, _)| *start),
+        )
+    }
+}
+
+impl FromStr for RateProfile {
+    type Err = String;
+
+    /// Accepts a single rate, like `0.5`, or a `<seconds>:<rate>` list separated by commas,
+    /// like `0:0.5,30:3,90:1`, starting at second 0. The last rate must be positive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Tasa de llegadas inválida: {}", s);
+        let mut profile = Vec::new();
+        for segment in s.split(',') {
+            let (start, rate) = match segment.split_once(':') {
+                Some((start, rate)) => (start.trim().parse().map_err(|_| invalid())?, rate),
+                None => (0.0, segment),
+            };
+            let rate: f64 = rate.trim().parse().map_err(|_| invalid())?;
+            if rate < 0.0 || !rate.is_finite() || start < 0.0 || !f64::is_finite(start) {
+                return Err(invalid());
+            }
+            let start = Duration::from_secs_f64(start);
+            if profile
+                .last()
+                .is_some_and(|(previous, _)| *previous >= start)
+            {
+                return Err(format!(
+                    "Tasa de llegadas inválida: {} (los tiempos deben ser crecientes)",
+                    s
+                ));
+            }
+            profile.push((start, rate));
+        }
+        match (profile.first(), profile.last()) {
+            (Some((first, _)), _) if !first.is_zero() => Err(format!(
+                "Tasa de llegadas inválida: {} (debe empezar en el segundo 0)",
+                s
+            )),
+            (_, Some((_, last))) if *last > 0.0 => Ok(Self(profile)),
+            _ => Err(format!(
+                "Tasa de llegadas inválida: {} (la última tasa debe ser positiva)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// When the orders without an arrival time reach the machine.
+pub enum ArrivalProcess {
+    /// One order every given interval, the first one right away
+    Fixed(Duration),
+    /// Poisson arrivals, with a rate that can change over time
+    Poisson(RateProfile),
+}
+
+/// Decides when each order reaches the machine, and waits until then.
+/// Orders with an arrival time are replayed keeping the time between them, counted from the
+/// first one, and the rest follow the arrival process.
+pub struct ArrivalClock {
+    process: ArrivalProcess,
+    /// Replay speed of the arrival times: 2 replays them twice as fast
+    speed: f64,
+    rng: StdRng,
+    started: Instant,
+    /// Arrival time of the first order that had one, and when it reached the machine
+    first_arrival: Option<(Duration, Instant)>,
+    last_arrival: Option<Instant>,
+}
+
+impl ArrivalClock {
+    /// Creates the clock of a reader that starts now. With a seed, the Poisson arrivals are
+    /// reproducible.
+    pub fn new(process: ArrivalProcess, speed: f64, seed: Option<u64>) -> Self {
+        Self {
+            process,
+            speed,
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            started: Instant::now(),
+            first_arrival: None,
+            last_arrival: None,
+        }
+    }
+
+    /// Waits until the order reaches the machine, or until `stop` is set.
+    /// Returns False if it stopped waiting because of `stop`.
+    pub fn wait_for(&mut self, order: &Order, stop: &AtomicBool) -> bool {
+        let arrival = self.next_arrival(order);
+        while let Some(remaining) = arrival.checked_duration_since(Instant::now()) {
+            if stop.load(Ordering::Relaxed) {
+                return false;
+            }
+            thread::sleep(remaining.min(Duration::from_millis(ARRIVAL_CHECK_INTERVAL)));
+        }
+        self.last_arrival = Some(arrival.max(self.last_arrival.unwrap_or(arrival)));
+        true
+    }
+
+    /// Returns the moment the order reaches the machine.
+    fn next_arrival(&mut self, order: &Order) -> Instant {
+        if let Some(arrival_time) = order.get_details().arrival_time {
+            let (first_time, first_instant) = *self
+                .first_arrival
+                .get_or_insert_with(|| (arrival_time, Instant::now()));
+            // Los pedidos con una hora anterior a la del primero llegan en el momento.
+            let offset = arrival_time.saturating_sub(first_time).div_f64(self.speed);
+            return first_instant + offset;
+        }
+        match &self.process {
+            ArrivalProcess::Fixed(interval) => match self.last_arrival {
+                Some(last_arrival) => last_arrival + *interval,
+                None => Instant::now(),
+            },
+            ArrivalProcess::Poisson(profile) => {
+                let from = self.last_arrival.unwrap_or(self.started);
+                let gap = poisson_gap(profile, from - self.started, &mut self.rng);
+                from + gap
+            }
+        }
+    }
+}
+
+/// Returns the time from `elapsed` until the next arrival of a Poisson process with the rates
+/// of the profile. Since the process has no memory, whenever the rate changes before the
+/// arrival the time is drawn again from that moment with the new rate.
+pub fn poisson_gap(profile: &RateProfile, elapsed: Duration, rng: &mut impl Rng) -> Duration {
+    let mut at = elapsed;
+    loop {
+        let (rate, next_start) = profile.rate_at(at);
+        let gap = if rate > 0.0 {
+            Duration::try_from_secs_f64(-(1.0 - rng.gen::<f64>()).ln() / rate)
+                .unwrap_or(Duration::MAX)
+        } else {
+            Duration::MAX
+        };
+        match next_start {
+            Some(next_start) if at.saturating_add(gap) > next_start => at = next_start,
+            _ => return at.saturating_add(gap) - elapsed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order::OrderDetails;
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(7)
+    }
+
+    /// Creates an order that arrived at the given second, if any.
+    fn order(arrival_time: Option<u64>) -> Order {
+        let details = OrderDetails {
+            arrival_time: arrival_time.map(Duration::from_secs),
+            ..OrderDetails::default()
+        };
+        Order::new(1, 1, 1, None).with_details(details)
+    }
+
+    #[test]
+    fn a_rate_profile_starts_at_second_zero_and_ends_with_a_positive_rate() {
+        assert!("0:0.5,30:3,90:1".parse::<RateProfile>().is_ok());
+        assert!("5:1".parse::<RateProfile>().is_err());
+        assert!("0:1,30:0".parse::<RateProfile>().is_err());
+        assert!("0:1,30:2,10:1".parse::<RateProfile>().is_err());
+    }
+
+    #[test]
+    fn no_order_arrives_while_the_rate_is_zero() {
+        let profile: RateProfile = "0:0,10:1".parse().expect("Failed to parse rate");
+        let mut rng = rng();
+        for _ in 0..100 {
+            assert!(poisson_gap(&profile, Duration::ZERO, &mut rng) >= Duration::from_secs(10));
+            assert!(
+                poisson_gap(&profile, Duration::from_secs(4), &mut rng) >= Duration::from_secs(6)
+            );
+        }
+    }
+
+    #[test]
+    fn the_gaps_follow_the_rate_of_each_part_of_the_profile() {
+        let profile: RateProfile = "0:4,100:0.5".parse().expect("Failed to parse rate");
+        let mut rng = rng();
+        let mean_gap = |elapsed: u64, rng: &mut StdRng| {
+            let total: Duration = (0..10_000)
+                .map(|_| poisson_gap(&profile, Duration::from_secs(elapsed), rng))
+                .sum();
+            total.as_secs_f64() / 10_000.0
+        };
+
+        let early = mean_gap(0, &mut rng);
+        let late = mean_gap(200, &mut rng);
+        assert!((0.24..0.26).contains(&early), "mean gap {}", early);
+        assert!((1.9..2.1).contains(&late), "mean gap {}", late);
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_poisson_arrivals() {
+        let process = ArrivalProcess::Poisson("2".parse().expect("Failed to parse rate"));
+        let mut clocks = [
+            ArrivalClock::new(process.clone(), 1.0, Some(7)),
+            ArrivalClock::new(process, 1.0, Some(7)),
+        ];
+        for _ in 0..5 {
+            let [first, second] = clocks.each_mut().map(|clock| {
+                let arrival = clock.next_arrival(&order(None));
+                arrival - clock.started
+            });
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn arrival_times_are_replayed_from_the_first_one_at_the_replay_speed() {
+        let mut clock = ArrivalClock::new(ArrivalProcess::Fixed(Duration::ZERO), 2.0, None);
+
+        let first = clock.next_arrival(&order(Some(10)));
+        let second = clock.next_arrival(&order(Some(14)));
+        let earlier = clock.next_arrival(&order(Some(3)));
+
+        assert_eq!(second - first, Duration::from_secs(2));
+        assert_eq!(earlier, first);
+    }
+}
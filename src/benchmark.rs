@@ -0,0 +1,152 @@
+use crate::config::Config;
+use crate::constants::{COLOR_RESET, COLOR_YELLOW};
+use crate::dispatcher::{Dispatcher, DispatcherKind};
+use crate::dispenser::{Dispenser, DispenserSpec};
+use crate::order::Order;
+use crate::scheduling_policy::{RefillStatus, SchedulingPolicy};
+use crate::stats::{LatencyStats, LatencySummary};
+use crate::utils::Message;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Result of running the benchmark over a single dispatcher.
+struct BenchmarkResult {
+    kind: DispatcherKind,
+    elapsed: Duration,
+    latency: LatencySummary,
+}
+
+/// Measures how each dispatcher behaves when `orders` orders arrive all at once.
+///
+/// Dispensers take the orders without preparing them, so the time measured is the one spent
+/// synchronizing the reader and the dispensers. The amount of dispensers and the scheduling
+/// policy are taken from the configuration.
+pub fn benchmark_dispatchers(config: &Config, orders: u64) -> String {
+    let results: Vec<BenchmarkResult> = DispatcherKind::ALL
+        .iter()
+        .map(|kind| run_benchmark(*kind, config, orders))
+        .collect();
+
+    let mut report = format!(
+        "{}[Benchmark de dispatchers]{} - {} pedidos, {} dispensers, política {}\n{:<16}{:>12}{:>16}{:>12}{:>12}\n",
+        COLOR_YELLOW,
+        COLOR_RESET,
+        orders,
+        config.dispensers.len(),
+        config.policy,
+        "dispatcher",
+        "tiempo",
+        "pedidos/s",
+        "espera",
+        "p99"
+    );
+    for result in results {
+        report.push_str(&format!(
+            "{:<16}{:>11.3}s{:>16.0}{:>10.3}ms{:>10.3}ms\n",
+            result.kind.to_string(),
+            result.elapsed.as_secs_f64(),
+            orders as f64 / result.elapsed.as_secs_f64(),
+            result.latency.mean.as_secs_f64() * 1000.0,
+            result.latency.p99.as_secs_f64() * 1000.0
+        ));
+    }
+    report
+}
+
+/// Pushes every order to a new dispatcher of the given kind and waits until all are taken
+fn run_benchmark(kind: DispatcherKind, config: &Config, orders: u64) -> BenchmarkResult {
+    let dispatcher = kind.build();
+    let policy: Arc<dyn SchedulingPolicy> = Arc::from(config.policy.build());
+    let refill_status = Arc::new(RefillStatus::new());
+    let latency_stats = Arc::new(Mutex::new(LatencyStats::new()));
+    let dispensers: Vec<Dispenser> = (1..=config.dispensers.len() as u64)
+        .map(|id| Dispenser::new(id, DispenserSpec::default()))
+        .collect();
+    for dispenser in &dispensers {
+        dispatcher.register(dispenser);
+    }
+
+    let start = Instant::now();
+    let consumers: Vec<_> = dispensers
+        .into_iter()
+        .map(|dispenser| {
+            let dispatcher = dispatcher.clone();
+            let policy = policy.clone();
+            let refill_status = refill_status.clone();
+            let latency_stats = latency_stats.clone();
+            thread::spawn(move || {
+                take_orders(
+                    dispatcher.as_ref(),
+                    &dispenser,
+                    policy.as_ref(),
+                    &refill_status,
+                    &latency_stats,
+                )
+            })
+        })
+        .collect();
+    for _ in 0..orders {
+        dispatcher.push(Message::Job(Order::new(1, 1, 1, None)));
+    }
+    for _ in 0..consumers.len() {
+        dispatcher.push(Message::Shutdown);
+    }
+    for consumer in consumers {
+        consumer.join().expect("Failed to join benchmark consumer");
+    }
+    let elapsed = start.elapsed();
+
+    let latency = latency_stats
+        .lock()
+        .expect("Failed to lock latency_stats")
+        .summary();
+    BenchmarkResult {
+        kind,
+        elapsed,
+        latency,
+    }
+}
+
+/// Takes orders until a shutdown message arrives, recording how long each one waited
+fn take_orders(
+    dispatcher: &dyn Dispatcher,
+    dispenser: &Dispenser,
+    policy: &dyn SchedulingPolicy,
+    refill_status: &RefillStatus,
+    latency_stats: &Mutex<LatencyStats>,
+) {
+    let mut latencies = Vec::new();
+    while let Message::Job(order) = dispatcher.pop(dispenser, policy, refill_status) {
+        latencies.push(order.get_arrival_time().elapsed());
+    }
+    let mut latency_stats = latency_stats.lock().expect("Failed to lock latency_stats");
+    for latency in latencies {
+        latency_stats.record(latency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_order_of_the_benchmark_is_taken_by_each_dispatcher() {
+        let config = Config::default();
+
+        for kind in DispatcherKind::ALL {
+            let result = run_benchmark(kind, &config, 200);
+            assert_eq!(result.latency.count, 200);
+        }
+    }
+
+    #[test]
+    fn the_benchmark_report_has_a_row_per_dispatcher() {
+        let report = benchmark_dispatchers(&Config::default(), 50);
+        let rows: Vec<&str> = report.lines().skip(2).collect();
+
+        assert_eq!(rows.len(), DispatcherKind::ALL.len());
+        assert!(rows[0].starts_with("shared"));
+        assert!(rows[1].starts_with("work-stealing"));
+    }
+}
@@ -28,15 +28,40 @@ impl<T> BlockingQueue<T> {
         self.cvar.notify_all();
     }
 
-    /// Pop element from front of queue
-    pub fn pop_front(&self) -> T {
+    /// Pop the element chosen by `select` from the queue
+    ///
+    /// `select` receives the queue and returns the index of the element to pop, if any.
+    /// Blocks while there is no element that can be chosen.
+    pub fn pop_by<F>(&self, mut select: F) -> T
+    where
+        F: FnMut(&VecDeque<T>) -> Option<usize>,
+    {
+        let mut index = None;
         let mut dequeue = self
             .cvar
             .wait_while(
                 self.dequeue.lock().expect("Failed to lock dequeue"),
-                |dequeue| dequeue.is_empty(),
+                |dequeue| {
+                    index = select(dequeue);
+                    index.is_none()
+                },
             )
-            .expect("Failed to wait while dequeue is empty");
-        dequeue.pop_front().expect("Failed to pop front of dequeue")
+            .expect("Failed to wait while there is nothing to choose");
+        let index = index.expect("Failed to choose an element");
+        dequeue
+            .remove(index)
+            .expect("Failed to remove from dequeue")
+    }
+
+    /// Runs `inspect` over the current elements of the queue without removing them
+    pub fn inspect<R>(&self, inspect: impl FnOnce(&VecDeque<T>) -> R) -> R {
+        let dequeue = self.dequeue.lock().expect("Failed to lock dequeue");
+        inspect(&dequeue)
+    }
+
+    /// Removes and returns every element left in the queue
+    pub fn drain(&self) -> Vec<T> {
+        let mut dequeue = self.dequeue.lock().expect("Failed to lock dequeue");
+        dequeue.drain(..).collect()
     }
 }
@@ -0,0 +1,194 @@
+use super::CoffeeMachine;
+use crate::alert::{Alert, AlertEvent, Severity};
+use crate::constants::{COLOR_RED, COLOR_RESET};
+use crate::container::{ContainerKind, Signal};
+use crate::stats::AlertStats;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+impl CoffeeMachine {
+    /// Watches the level of the container of the given kind
+    /// It alerts once when the level goes under the threshold of a severity, instead of on every
+    /// change, and again if it gets worse. The alert is cleared once the level is back above the
+    /// thresholds, and while it is not, a reminder is printed every `alert_reminder` if configured
+    fn watch_level(&self, kind: ContainerKind) {
+        let container = self.container(kind);
+        let is_shutting_down = || self.should_shutdown.load(Ordering::Relaxed);
+        let mut current = container.get_severity();
+        if let Some(severity) = current {
+            self.raise_alert(kind, severity, *container.lock().get_current_amount());
+        }
+        loop {
+            let (level, timed_out) = container.wait_timeout_while(
+                Signal::AlertLevelChanged,
+                container.lock(),
+                current.and(self.alert_reminder),
+                |_| container.get_severity() == current && !is_shutting_down(),
+            );
+            if is_shutting_down() {
+                break;
+            }
+            let amount = *level.get_current_amount();
+            drop(level);
+            let severity = container.get_severity();
+            match severity {
+                Some(severity) if timed_out => {
+                    self.emit_alert(
+                        kind,
+                        AlertEvent::Reminder,
+                        Some(severity),
+                        amount,
+                        format!(
+                            "Recordatorio: {}, el nivel sigue siendo {}",
+                            severity, amount
+                        ),
+                    );
+                    self.update_alert_stats(kind, AlertStats::record_reminder);
+                }
+                Some(severity) if Some(severity) > current => {
+                    self.raise_alert(kind, severity, amount)
+                }
+                Some(severity) => self.emit_alert(
+                    kind,
+                    AlertEvent::Lowered,
+                    Some(severity),
+                    amount,
+                    format!("El nivel subió a {}, la alerta bajó a {}", amount, severity),
+                ),
+                None => {
+                    self.emit_alert(
+                        kind,
+                        AlertEvent::Cleared,
+                        None,
+                        amount,
+                        format!("El nivel volvió a {}, alerta resuelta", amount),
+                    );
+                    self.update_alert_stats(kind, AlertStats::record_cleared);
+                }
+            }
+            current = severity;
+        }
+        println!(
+            "{}[Alerta de recursos: {}]{} - Apagando alerta de recursos de {}",
+            COLOR_RED, kind, COLOR_RESET, kind
+        );
+    }
+
+    /// Raises an alert of the given severity for the container of the given kind
+    fn raise_alert(&self, kind: ContainerKind, severity: Severity, amount: u64) {
+        self.emit_alert(
+            kind,
+            AlertEvent::Raised,
+            Some(severity),
+            amount,
+            format!(
+                "{}: el nivel es de {} (threshold de {})",
+                severity,
+                amount,
+                self.container(kind).get_levels().get_threshold(severity)
+            ),
+        );
+        self.update_alert_stats(kind, |stats| stats.record_raised(severity));
+    }
+
+    /// Prints an alert about the container of the given kind and queues it to be delivered
+    /// to the alert sinks
+    pub(super) fn emit_alert(
+        &self,
+        kind: ContainerKind,
+        event: AlertEvent,
+        severity: Option<Severity>,
+        amount: u64,
+        message: String,
+    ) {
+        println!(
+            "{}[Alerta de recursos: {}]{} - {}",
+            COLOR_RED, kind, COLOR_RESET, message
+        );
+        self.alert_notifier
+            .notify(Alert::new(kind, event, severity, amount, message));
+    }
+
+    /// Updates the alert stats of the given container
+    pub(super) fn update_alert_stats(
+        &self,
+        kind: ContainerKind,
+        update: impl FnOnce(&mut AlertStats),
+    ) {
+        let mut alert_stats = self.alert_stats.lock().expect("Failed to lock alert_stats");
+        update(alert_stats.entry(kind).or_insert_with(AlertStats::new));
+    }
+
+    /// Spawns a thread that will alert about the level of the container of the given kind
+    pub(super) fn alert_for(self: &Arc<Self>, kind: ContainerKind) -> JoinHandle<()> {
+        self.spawn_actor(format!("la alerta de {}", kind), move |coffee_machine| {
+            coffee_machine.watch_level(kind)
+        })
+    }
+
+    /// Spawns a thread that will deliver the alerts to the configured sinks
+    pub(super) fn deliver_alerts(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(
+            String::from("la notificación de alertas"),
+            |coffee_machine| coffee_machine.alert_notifier.deliver(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::Threshold;
+    use crate::config::Config;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn a_low_level_alert_is_raised_once_and_cleared_only_above_the_hysteresis() {
+        let mut config = Config {
+            alert_reminder: None,
+            ..Config::default()
+        };
+        let thresholds = &mut config.alert_thresholds[ContainerKind::ColdMilk as usize];
+        thresholds.set(Severity::Warning, Threshold::Absolute(20000));
+        let coffee_machine = CoffeeMachine::new(&config);
+        let watcher = coffee_machine.alert_for(ContainerKind::ColdMilk);
+        let container = coffee_machine.container(ContainerKind::ColdMilk);
+        let consume = |amount: u64| {
+            let mut level = container.lock();
+            level.subtract(&amount);
+            container.notify_consumption(&level);
+            drop(level);
+            thread::sleep(Duration::from_millis(50));
+        };
+        let refill = |amount: u64| {
+            let mut level = container.lock();
+            level.add(&amount);
+            container.notify_refill(&level);
+            drop(level);
+            thread::sleep(Duration::from_millis(50));
+        };
+
+        // El threshold es de 20000 y la histéresis de 10000
+        consume(85000);
+        consume(5000);
+        refill(15000);
+        refill(10000);
+        consume(20000);
+        coffee_machine
+            .should_shutdown
+            .store(true, Ordering::Relaxed);
+        container.notify_everyone();
+        watcher.join().expect("Failed to join alert watcher");
+
+        let alert_stats = coffee_machine
+            .alert_stats
+            .lock()
+            .expect("Failed to lock alert_stats");
+        assert_eq!(
+            alert_stats[&ContainerKind::ColdMilk].to_string(),
+            "ADVERTENCIA: 2 - CRÍTICO: 0 - VACÍO: 0 - Recordatorios: 0 - Resueltas: 1 - Agotamientos previstos: 0"
+        );
+    }
+}
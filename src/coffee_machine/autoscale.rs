@@ -0,0 +1,210 @@
+use super::CoffeeMachine;
+use crate::constants::{
+    AUTOSCALE_CHECK_INTERVAL, AUTOSCALE_MAX_WAIT, AUTOSCALE_ORDERS_PER_DISPENSER,
+    AUTOSCALE_SCALE_DOWN_COOLDOWN, COLOR_RESET, COLOR_YELLOW,
+};
+use crate::dispenser::{Dispenser, DispenserSpec};
+use crate::stats::{ScaleAction, ScaleEvent};
+use crate::utils::Message;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+impl CoffeeMachine {
+    /// Adds a dispenser with the given hardware to the pool and starts its thread
+    /// Returns the number of the new dispenser, or `None` if the pool is already closed
+    pub fn add_dispenser(self: &Arc<Self>, spec: DispenserSpec) -> Option<u64> {
+        let mut active_dispensers = self
+            .active_dispensers
+            .lock()
+            .expect("Failed to lock active_dispensers");
+        if self.pool_closed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut dispensers = self.dispensers.lock().expect("Failed to lock dispensers");
+        let dispenser = Dispenser::new(dispensers.len() as u64 + 1, spec);
+        dispensers.push(dispenser);
+        *active_dispensers += 1;
+        self.dispatcher.register(&dispenser);
+
+        self.spawn_dispenser(dispenser);
+        Some(dispenser.get_id())
+    }
+
+    /// Asks a dispenser to leave the pool once it finishes its current drink
+    /// Only a dispenser whose capabilities another active dispenser also has is retired, so that
+    /// every order the pool could make can still be made. The newest one is chosen
+    /// Returns the number of the retired dispenser, or `None` if the pool is already closed or
+    /// no dispenser can leave
+    pub fn retire_dispenser(&self) -> Option<u64> {
+        let mut active_dispensers = self
+            .active_dispensers
+            .lock()
+            .expect("Failed to lock active_dispensers");
+        if self.pool_closed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let dispensers = self.dispensers.lock().expect("Failed to lock dispensers");
+        let mut leaving = self.leaving.lock().expect("Failed to lock leaving");
+        let active: Vec<&Dispenser> = dispensers
+            .iter()
+            .filter(|dispenser| !leaving.contains(&dispenser.get_id()))
+            .collect();
+        let n_dispenser = active
+            .iter()
+            .rev()
+            .find(|candidate| {
+                active.iter().any(|other| {
+                    other.get_id() != candidate.get_id()
+                        && candidate
+                            .get_capabilities()
+                            .covered_by(other.get_capabilities())
+                })
+            })?
+            .get_id();
+        leaving.insert(n_dispenser);
+        *active_dispensers -= 1;
+        self.dispatcher.push(Message::Retire(n_dispenser));
+        Some(n_dispenser)
+    }
+
+    /// Spawns a thread that will scale the dispenser pool, if the autoscaler is enabled
+    pub(super) fn autoscaler(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let (min, max) = self.autoscale?;
+        Some(
+            self.spawn_actor(String::from("el autoscaler"), move |coffee_machine| {
+                coffee_machine.autoscale(min, max)
+            }),
+        )
+    }
+
+    /// Periodically adds or retires dispensers according to the queue length and the time the
+    /// oldest order has been waiting, keeping the pool between `min` and `max` dispensers
+    /// Scaling up adds a dispenser with the default hardware (`DispenserSpec::default()`), which
+    /// applies every ingredient at the base speed. A dispenser is retired only once the queue
+    /// stayed empty for `AUTOSCALE_SCALE_DOWN_COOLDOWN`, and the cooldown starts again after that
+    fn autoscale(self: &Arc<Self>, min: usize, max: usize) {
+        let cooldown = Duration::from_millis(AUTOSCALE_SCALE_DOWN_COOLDOWN);
+        let mut empty_since: Option<Instant> = None;
+        while !self.pool_closed.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(AUTOSCALE_CHECK_INTERVAL));
+            let waiting = self.dispatcher.waiting_orders();
+            let waiting_orders = waiting.len();
+            empty_since = match waiting_orders {
+                0 => Some(empty_since.unwrap_or_else(Instant::now)),
+                _ => None,
+            };
+            let oldest_wait = waiting
+                .iter()
+                .map(|order| order.get_arrival_time().elapsed())
+                .max()
+                .unwrap_or_default();
+            let active_dispensers = *self
+                .active_dispensers
+                .lock()
+                .expect("Failed to lock active_dispensers");
+
+            let scale_up_reason =
+                Self::scale_up_reason(active_dispensers, min, max, waiting_orders, oldest_wait);
+            let empty_for = empty_since.map(|since| since.elapsed());
+            let scale_down_reason = if active_dispensers > max {
+                Some(String::from("por encima del máximo"))
+            } else if active_dispensers > min && empty_for.is_some_and(|empty| empty >= cooldown) {
+                empty_for.map(|empty| {
+                    format!("no hay pedidos en espera hace {:.2}s", empty.as_secs_f64())
+                })
+            } else {
+                None
+            };
+
+            if let Some(reason) = scale_up_reason {
+                if self.add_dispenser(DispenserSpec::default()).is_some() {
+                    self.record_scale_event(ScaleAction::Up, active_dispensers + 1, reason);
+                }
+            } else if let Some(reason) = scale_down_reason {
+                if let Some(n_dispenser) = self.retire_dispenser() {
+                    self.record_scale_event(
+                        ScaleAction::Down,
+                        active_dispensers - 1,
+                        format!("{}, se retira el dispenser {}", reason, n_dispenser),
+                    );
+                    empty_since = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    /// Returns why a dispenser should be added to the pool, if it should: the pool is below `min`,
+    /// or below `max` while there are too many orders waiting or the oldest one waited too long
+    fn scale_up_reason(
+        active_dispensers: usize,
+        min: usize,
+        max: usize,
+        waiting_orders: usize,
+        oldest_wait: Duration,
+    ) -> Option<String> {
+        if active_dispensers < min {
+            Some(String::from("por debajo del mínimo"))
+        } else if active_dispensers >= max {
+            None
+        } else if waiting_orders > active_dispensers * AUTOSCALE_ORDERS_PER_DISPENSER {
+            Some(format!("{} pedidos en espera", waiting_orders))
+        } else if oldest_wait > Duration::from_millis(AUTOSCALE_MAX_WAIT) {
+            Some(format!(
+                "el pedido más antiguo espera hace {:.2}s",
+                oldest_wait.as_secs_f64()
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Records a change in the size of the dispenser pool
+    fn record_scale_event(&self, action: ScaleAction, active_dispensers: usize, reason: String) {
+        let event = ScaleEvent {
+            elapsed: self.started_at.elapsed(),
+            action,
+            active_dispensers,
+            reason,
+        };
+        println!("{}[Autoscaler]{} - {}", COLOR_YELLOW, COLOR_RESET, event);
+        self.scale_events
+            .lock()
+            .expect("Failed to lock scale_events")
+            .push(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn only_a_dispenser_whose_capabilities_another_one_has_is_retired() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        let spec = |spec: &str| spec.parse().expect("Failed to parse dispenser");
+        coffee_machine.add_dispenser(spec("water-only"));
+        coffee_machine.add_dispenser(spec("all"));
+        coffee_machine.add_dispenser(spec("no-milk"));
+
+        assert_eq!(coffee_machine.retire_dispenser(), Some(3));
+        assert_eq!(coffee_machine.retire_dispenser(), Some(1));
+        assert_eq!(coffee_machine.retire_dispenser(), None);
+    }
+
+    #[test]
+    fn the_pool_scales_up_when_orders_pile_up_or_wait_too_long_until_the_maximum() {
+        let max_wait = Duration::from_millis(AUTOSCALE_MAX_WAIT);
+        let piled_up = 2 * AUTOSCALE_ORDERS_PER_DISPENSER + 1;
+
+        assert!(CoffeeMachine::scale_up_reason(1, 2, 4, 0, Duration::ZERO).is_some());
+        assert!(CoffeeMachine::scale_up_reason(2, 1, 4, piled_up, Duration::ZERO).is_some());
+        assert!(CoffeeMachine::scale_up_reason(2, 1, 4, piled_up - 1, Duration::ZERO).is_none());
+        assert!(CoffeeMachine::scale_up_reason(2, 1, 4, 1, max_wait * 2).is_some());
+        assert!(CoffeeMachine::scale_up_reason(2, 1, 4, 1, max_wait).is_none());
+        assert!(CoffeeMachine::scale_up_reason(4, 1, 4, piled_up * 2, max_wait * 2).is_none());
+    }
+}
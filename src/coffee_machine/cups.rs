@@ -0,0 +1,116 @@
+use super::CoffeeMachine;
+use crate::constants::{COLOR_GREEN, COLOR_RESET, CUP_WAIT_TIMEOUT};
+use crate::container::Signal;
+use crate::dispenser::Dispenser;
+use crate::Order;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+impl CoffeeMachine {
+    /// Takes a cup of the size of the order
+    /// If there are none, it waits for a delivery of cups until the customer leaves, or for
+    /// `CUP_WAIT_TIMEOUT` if the customer has no deadline, and discards the order if none arrives
+    /// Returns True if a cup was taken
+    pub(super) fn take_cup(&self, order: &Order, dispenser: &Dispenser) -> bool {
+        let size = order.get_size();
+        let container = self.container(size.cups());
+        let mut cups = container.lock();
+        if !cups.has_any() {
+            println!(
+                "{}[Dispenser {}]{} - No hay vasos de tamaño {}, esperando una entrega",
+                COLOR_GREEN,
+                dispenser.get_id(),
+                COLOR_RESET,
+                size
+            );
+            let timeout = match order.deadline() {
+                Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+                None => Duration::from_millis(CUP_WAIT_TIMEOUT),
+            };
+            (cups, _) = container.wait_timeout_while(
+                Signal::RefillCompleted,
+                cups,
+                Some(timeout),
+                |cups| !cups.has_any() && !self.should_shutdown.load(Ordering::Relaxed),
+            );
+        }
+        if !cups.has_any() {
+            println!(
+                "{}[Dispenser {}]{} - Sin vasos de tamaño {}, descartando pedido: {}",
+                COLOR_GREEN,
+                dispenser.get_id(),
+                COLOR_RESET,
+                size,
+                order
+            );
+            self.size_stats
+                .lock()
+                .expect("Failed to lock size_stats")
+                .record_out_of_cups(size);
+            return false;
+        }
+        cups.subtract(&1);
+        container.notify_consumption(&cups);
+        true
+    }
+
+    /// Puts back the cup of an order that is not prepared, if it already took one
+    pub(super) fn return_cup(&self, order: &Order) {
+        if !order.has_cup() {
+            return;
+        }
+        let container = self.container(order.get_size().cups());
+        let mut cups = container.lock();
+        cups.put_back(&1);
+        container.notify_refill(&cups);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coffee_machine::tests::{dispenser, order};
+    use crate::config::Config;
+    use crate::order::Size;
+    use std::thread;
+
+    /// Takes every cup of the size out of its container.
+    fn run_out_of_cups(coffee_machine: &CoffeeMachine, size: Size) {
+        let mut cups = coffee_machine.container(size.cups()).lock();
+        let amount = *cups.get_current_amount();
+        cups.subtract(&amount);
+    }
+
+    #[test]
+    fn without_cups_a_dispenser_waits_for_a_delivery_of_them() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        run_out_of_cups(&coffee_machine, Size::Large);
+        let delivery = {
+            let coffee_machine = coffee_machine.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                let container = coffee_machine.container(Size::Large.cups());
+                let mut cups = container.lock();
+                cups.add(&2);
+                container.notify_refill(&cups);
+            })
+        };
+
+        let order = order(Size::Large, Some(Duration::from_secs(5)));
+        assert!(coffee_machine.take_cup(&order, &dispenser()));
+        delivery.join().expect("Failed to deliver cups");
+        let cups = coffee_machine.container(Size::Large.cups()).lock();
+        assert_eq!(*cups.get_current_amount(), 1);
+    }
+
+    #[test]
+    fn an_order_is_discarded_if_no_cups_arrive_before_the_customer_leaves() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        run_out_of_cups(&coffee_machine, Size::Small);
+
+        let order = order(Size::Small, Some(Duration::from_millis(50)));
+        assert!(!coffee_machine.take_cup(&order, &dispenser()));
+        let size_stats = coffee_machine.size_stats.lock().unwrap().to_string();
+        assert!(size_stats.ends_with("Sin vaso: chico: 1 - mediano: 0 - grande: 0"));
+    }
+}
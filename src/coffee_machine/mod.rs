@@ -0,0 +1,1621 @@
+mod alerts;
+mod autoscale;
+mod cups;
+mod supervision;
+mod supply;
+
+use crate::alert::AlertNotifier;
+use crate::config::Config;
+use crate::constants::{
+    COFFEE_TO_REFILL, COLOR_BLUE, COLOR_CYAN, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_RESET,
+    COLOR_YELLOW, MILK_TO_REFILL,
+};
+use crate::container::{ContainerKind, ContainerMonitor, Signal};
+use crate::delivery::Delivery;
+use crate::dispatcher::Dispatcher;
+use crate::dispenser::{Dispenser, DispenserSpec};
+use crate::forecast::Forecast;
+use crate::order::Size;
+use crate::order_input::OrderInput;
+use crate::order_reader::{reader_name, OrderReader};
+use crate::scheduling_policy::{RefillStatus, SchedulingPolicy};
+use crate::shutdown::{panic_message, persist_orders, signal_name, Outcome, StopReason};
+use crate::stats::{
+    AlertStats, CrashStats, DeliveryRecord, DispenserStats, DrinkStats, LatencyStats,
+    LatencySummary, PurchaseRecord, ScaleEvent, SizeStats, SlaStats, SourceStats, StepStats,
+    SubstitutionStats,
+};
+use crate::substitution::{Reservation, Substitutes};
+use crate::supplier::Supplier;
+use crate::utils::converter::{refill_coffee, refill_milk};
+use crate::utils::{Message, Resource};
+use crate::{Order, BASE_TIME_RESOURCE_APPLICATION, STATS_UPDATE_INTERVAL};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// An order a dispenser is preparing, with what is still set aside for it, so that only that is
+/// given back if the dispenser crashes
+struct InFlightOrder {
+    order: Order,
+    /// Amount of each ingredient set aside for the order and not served yet
+    reserved: [u64; 3],
+}
+
+/// Represents a coffee machine, with its corresponding containers and dispensers
+pub struct CoffeeMachine {
+    coffee_beans_to_grind_container: Arc<ContainerMonitor>,
+    ground_coffee_beans_container: Arc<ContainerMonitor>,
+    cold_milk_container: Arc<ContainerMonitor>,
+    milk_foam_container: Arc<ContainerMonitor>,
+    cup_containers: [Arc<ContainerMonitor>; 3],
+    total_drinks_prepared: Arc<Mutex<u64>>,
+    dispatcher: Arc<dyn Dispatcher>,
+    should_shutdown: Arc<AtomicBool>,
+    scheduling_policy: Box<dyn SchedulingPolicy>,
+    refill_status: RefillStatus,
+    latency_stats: Mutex<LatencyStats>,
+    sla_stats: Mutex<SlaStats>,
+    drink_stats: Mutex<DrinkStats>,
+    step_stats: Mutex<StepStats>,
+    size_stats: Mutex<SizeStats>,
+    substitutes: Substitutes,
+    /// Amount of each ingredient set aside for the drinks being prepared
+    reserved: Mutex<[u64; 3]>,
+    substitution_stats: Mutex<SubstitutionStats>,
+    initial_dispensers: Vec<DispenserSpec>,
+    dispensers: Mutex<Vec<Dispenser>>,
+    /// Dispensers asked to retire or left out of service, which no longer count as active
+    leaving: Mutex<BTreeSet<u64>>,
+    dispenser_handles: Mutex<Vec<(Dispenser, JoinHandle<()>)>>,
+    in_flight: Mutex<BTreeMap<u64, InFlightOrder>>,
+    max_restarts: u64,
+    crash_stats: Mutex<CrashStats>,
+    active_dispensers: Mutex<usize>,
+    pool_closed: AtomicBool,
+    dispenser_stats: Mutex<BTreeMap<u64, DispenserStats>>,
+    autoscale: Option<(usize, usize)>,
+    scale_events: Mutex<Vec<ScaleEvent>>,
+    started_at: Instant,
+    alert_reminder: Option<Duration>,
+    alert_stats: Mutex<BTreeMap<ContainerKind, AlertStats>>,
+    alert_notifier: AlertNotifier,
+    forecasts: Mutex<[Forecast; ContainerKind::COUNT]>,
+    forecast_horizon: Option<Duration>,
+    pending_deliveries: Mutex<Vec<Delivery>>,
+    delivery_records: Mutex<Vec<DeliveryRecord>>,
+    reorder_points: [Option<u64>; ContainerKind::COUNT],
+    supplier: Box<dyn Supplier>,
+    purchase_records: Mutex<Vec<PurchaseRecord>>,
+    intake_closed: Arc<AtomicBool>,
+    order_reader: OrderReader,
+    /// Inputs still being read. Once the last one runs out, the dispensers are told to stop
+    open_inputs: AtomicUsize,
+    source_stats: Arc<Mutex<SourceStats>>,
+    stop_reason: Mutex<Option<(StopReason, Instant)>>,
+    drain_timeout: Duration,
+    unstarted_orders: Mutex<Vec<Order>>,
+    unstarted_orders_path: PathBuf,
+    report_path: Option<PathBuf>,
+}
+
+impl CoffeeMachine {
+    /// Creates a new coffee machine with the given configuration
+    pub fn new(config: &Config) -> Arc<Self> {
+        let dispatcher = config.dispatcher.build();
+        let intake_closed = Arc::new(AtomicBool::new(false));
+        let source_stats = Arc::new(Mutex::new(SourceStats::new()));
+        Arc::new(Self {
+            coffee_beans_to_grind_container: Self::build_container(
+                config,
+                ContainerKind::CoffeeBeans,
+            ),
+            ground_coffee_beans_container: Self::build_container(
+                config,
+                ContainerKind::GroundCoffee,
+            ),
+            cold_milk_container: Self::build_container(config, ContainerKind::ColdMilk),
+            milk_foam_container: Self::build_container(config, ContainerKind::MilkFoam),
+            cup_containers: Size::ALL.map(|size| Self::build_container(config, size.cups())),
+            total_drinks_prepared: Arc::new(Mutex::new(0)),
+            order_reader: OrderReader::new(
+                dispatcher.clone(),
+                intake_closed.clone(),
+                source_stats.clone(),
+                config,
+            ),
+            open_inputs: AtomicUsize::new(0),
+            source_stats,
+            dispatcher,
+            should_shutdown: Arc::new(AtomicBool::new(false)),
+            scheduling_policy: config.policy.build(),
+            refill_status: RefillStatus::new(),
+            latency_stats: Mutex::new(LatencyStats::new()),
+            sla_stats: Mutex::new(SlaStats::new()),
+            drink_stats: Mutex::new(DrinkStats::new()),
+            step_stats: Mutex::new(StepStats::new()),
+            size_stats: Mutex::new(SizeStats::new()),
+            substitutes: Substitutes::new(&config.substitutes),
+            reserved: Mutex::new([0; 3]),
+            substitution_stats: Mutex::new(SubstitutionStats::new()),
+            initial_dispensers: config.dispensers.clone(),
+            dispensers: Mutex::new(Vec::new()),
+            leaving: Mutex::new(BTreeSet::new()),
+            dispenser_handles: Mutex::new(Vec::new()),
+            in_flight: Mutex::new(BTreeMap::new()),
+            max_restarts: config.max_restarts,
+            crash_stats: Mutex::new(CrashStats::new()),
+            active_dispensers: Mutex::new(0),
+            pool_closed: AtomicBool::new(false),
+            dispenser_stats: Mutex::new(BTreeMap::new()),
+            autoscale: config.autoscale,
+            scale_events: Mutex::new(Vec::new()),
+            started_at: Instant::now(),
+            alert_reminder: config.alert_reminder,
+            alert_stats: Mutex::new(BTreeMap::new()),
+            alert_notifier: AlertNotifier::new(
+                config.alert_sinks.iter().map(|sink| sink.build()).collect(),
+                config.alert_rate_limit,
+            ),
+            forecasts: Mutex::new([(); ContainerKind::COUNT].map(|_| Forecast::new())),
+            forecast_horizon: config.forecast_horizon,
+            pending_deliveries: Mutex::new(config.deliveries.clone()),
+            delivery_records: Mutex::new(Vec::new()),
+            reorder_points: ContainerKind::ALL.map(|kind| {
+                config.reorder_points[kind as usize]
+                    .map(|threshold| threshold.resolve(kind.capacity()))
+            }),
+            supplier: config.supplier.build(),
+            purchase_records: Mutex::new(Vec::new()),
+            intake_closed,
+            stop_reason: Mutex::new(None),
+            drain_timeout: config.drain_timeout,
+            unstarted_orders: Mutex::new(Vec::new()),
+            unstarted_orders_path: config.unstarted_orders_path.clone(),
+            report_path: config.report_path.clone(),
+        })
+    }
+
+    /// Creates a full container of the given kind, with its configured alert thresholds
+    fn build_container(config: &Config, kind: ContainerKind) -> Arc<ContainerMonitor> {
+        let capacity = kind.capacity();
+        Arc::new(ContainerMonitor::new(
+            capacity,
+            config.alert_thresholds[kind as usize].resolve(capacity),
+            config.shared_signalling,
+        ))
+    }
+
+    /// Returns the container of the given kind
+    fn container(&self, kind: ContainerKind) -> &ContainerMonitor {
+        match kind {
+            ContainerKind::GroundCoffee => &self.ground_coffee_beans_container,
+            ContainerKind::MilkFoam => &self.milk_foam_container,
+            ContainerKind::CoffeeBeans => &self.coffee_beans_to_grind_container,
+            ContainerKind::ColdMilk => &self.cold_milk_container,
+            ContainerKind::SmallCups => &self.cup_containers[Size::Small as usize],
+            ContainerKind::MediumCups => &self.cup_containers[Size::Medium as usize],
+            ContainerKind::LargeCups => &self.cup_containers[Size::Large as usize],
+        }
+    }
+
+    /// Public interface to start the coffee machine
+    /// This method will start the following threads:
+    /// - A thread per input to take its orders
+    /// - A thread to prepare the orders
+    /// - A thread to scale the dispenser pool, if the autoscaler is enabled
+    /// - A thread to inform the stats
+    /// - A thread per container to alert when its level goes under certain thresholds
+    /// - A thread to deliver the alerts to the configured sinks
+    /// - A thread to forecast when each container runs out
+    /// - A thread to receive the scheduled deliveries of raw materials, including the ones of
+    ///   the purchase orders placed when the forecasted stock is low
+    /// - A thread to handle the refill of coffee beans
+    /// - A thread to handle the refill of milk foam
+    ///
+    /// Returns how the run ended, which is a stop if a termination signal arrived or one of the
+    /// threads failed
+    pub fn start(self: &Arc<Self>, inputs: Vec<OrderInput>) -> Outcome {
+        self.prepare_orders();
+        let reader_handles = self.read_orders(inputs);
+        let autoscaler = self.autoscaler();
+        let milk_refill = self.refill_milk();
+        let coffee_refill = self.refill_coffee();
+        let alert_systems: Vec<JoinHandle<()>> = ContainerKind::ALL
+            .iter()
+            .map(|kind| self.alert_for(*kind))
+            .collect();
+        let alert_delivery = self.deliver_alerts();
+        let forecast_system = self.forecast_system();
+        let delivery_scheduler = self.delivery_scheduler();
+        let inform_system = self.inform_system();
+
+        let drained = self.supervise_dispensers();
+
+        // Debo avisarle a los threads que deben finalizar una vez que todos los threads terminaron
+        // sus pedidos.
+        self.should_shutdown.store(true, Ordering::Relaxed);
+        for kind in ContainerKind::ALL {
+            self.container(kind).notify_everyone();
+        }
+
+        coffee_refill
+            .join()
+            .expect("Failed to join coffee_refill thread");
+        milk_refill
+            .join()
+            .expect("Failed to join milk_refill thread");
+        forecast_system
+            .join()
+            .expect("Failed to join forecast_system thread");
+        delivery_scheduler
+            .join()
+            .expect("Failed to join delivery_scheduler thread");
+        for alert_system in alert_systems {
+            alert_system
+                .join()
+                .expect("Failed to join alert_system thread");
+        }
+        self.alert_notifier.close();
+        alert_delivery
+            .join()
+            .expect("Failed to join alert_delivery thread");
+        inform_system
+            .join()
+            .expect("Failed to join inform_system thread");
+        if let Some(autoscaler) = autoscaler {
+            autoscaler.join().expect("Failed to join autoscaler thread");
+        }
+
+        let stop_reason = self
+            .stop_reason
+            .lock()
+            .expect("Failed to lock stop_reason")
+            .take();
+        let outcome = match stop_reason {
+            // Los lectores pueden estar bloqueados esperando un pedido, así que no los espero.
+            Some((reason, _)) => Outcome::Stopped { reason, drained },
+            None => {
+                for reader_handle in reader_handles {
+                    reader_handle
+                        .join()
+                        .expect("Failed to join reader_handle thread");
+                }
+                Outcome::Completed
+            }
+        };
+
+        let report = self.final_report(&outcome);
+        println!("{}", report);
+        if let Some(path) = &self.report_path {
+            if let Err(error) = fs::write(path, strip_colors(&report)) {
+                println!(
+                    "{}[Estadísticas]{} - No se pudo escribir el reporte en {}: {}",
+                    COLOR_RED,
+                    COLOR_RESET,
+                    path.display(),
+                    error
+                );
+            }
+        }
+        outcome
+    }
+
+    /// Builds the report shown when the machine stops, ending with how the run ended
+    fn final_report(&self, outcome: &Outcome) -> String {
+        let mut lines = vec![
+            self.obtain_stats(),
+            format!(
+                "{}[Estadísticas]{} - {}",
+                COLOR_YELLOW,
+                COLOR_RESET,
+                self.latency_summary()
+            ),
+            format!(
+                "{}[Estadísticas]{} - {}",
+                COLOR_YELLOW,
+                COLOR_RESET,
+                self.sla_stats.lock().expect("Failed to lock sla_stats")
+            ),
+            self.obtain_dispenser_stats(),
+            format!(
+                "{}[Estadísticas]{} - {}",
+                COLOR_YELLOW,
+                COLOR_RESET,
+                self.step_stats.lock().expect("Failed to lock step_stats")
+            ),
+        ];
+        lines.push(format!(
+            "{}[Estadísticas]{} - {}",
+            COLOR_YELLOW,
+            COLOR_RESET,
+            self.size_stats.lock().expect("Failed to lock size_stats")
+        ));
+        if let Some(drink_stats) = self.obtain_drink_stats() {
+            lines.push(drink_stats);
+        }
+        if let Some(source_stats) = self.obtain_source_stats() {
+            lines.push(source_stats);
+        }
+        if let Some(substitution_stats) = self.obtain_substitution_stats() {
+            lines.push(substitution_stats);
+        }
+        if let Some(crash_stats) = self.obtain_crash_stats() {
+            lines.push(crash_stats);
+        }
+        if let Some(reject_stats) = self.obtain_reject_stats() {
+            lines.push(reject_stats);
+        }
+        lines.extend([self.obtain_wakeup_stats(), self.obtain_alert_stats()]);
+        if !self.alert_notifier.stats().is_empty() {
+            lines.push(self.obtain_sink_stats());
+        }
+        if self.autoscale.is_some() {
+            lines.push(self.obtain_scale_events());
+        }
+        if self.tracks_deliveries() {
+            lines.push(self.obtain_delivery_stats());
+        }
+        match outcome {
+            Outcome::Completed => lines.extend(self.report_unserved_orders()),
+            Outcome::Stopped { .. } => lines.push(self.save_unstarted_orders()),
+        }
+        lines.push(format!(
+            "{}[Estadísticas]{} - Resultado: {}",
+            COLOR_YELLOW, COLOR_RESET, outcome
+        ));
+        lines.join("\n")
+    }
+
+    /// Obtains the low level alerts raised for each resource
+    fn obtain_alert_stats(&self) -> String {
+        let alert_stats = self.alert_stats.lock().expect("Failed to lock alert_stats");
+        if alert_stats.is_empty() {
+            return format!(
+                "{}[Estadísticas]{} - No se emitieron alertas de recursos",
+                COLOR_YELLOW, COLOR_RESET
+            );
+        }
+        alert_stats
+            .iter()
+            .map(|(resource, stats)| {
+                format!(
+                    "{}[Estadísticas]{} - Alertas de {}: {}",
+                    COLOR_YELLOW, COLOR_RESET, resource, stats
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Returns True if any delivery was scheduled or can be scheduled by a purchase order
+    fn tracks_deliveries(&self) -> bool {
+        !self
+            .delivery_records
+            .lock()
+            .expect("Failed to lock delivery_records")
+            .is_empty()
+            || !self
+                .pending_deliveries
+                .lock()
+                .expect("Failed to lock pending_deliveries")
+                .is_empty()
+            || self.reorder_points.iter().any(Option::is_some)
+    }
+
+    /// Obtains the deliveries of raw materials received, how many did not arrive in time, and
+    /// the purchase orders placed
+    fn obtain_delivery_stats(&self) -> String {
+        let delivery_records = self
+            .delivery_records
+            .lock()
+            .expect("Failed to lock delivery_records");
+        let mut lines: Vec<String> = delivery_records
+            .iter()
+            .map(|record| {
+                format!(
+                    "{}[Estadísticas]{} - Entrega recibida: {}",
+                    COLOR_YELLOW, COLOR_RESET, record
+                )
+            })
+            .collect();
+        lines.push(format!(
+            "{}[Estadísticas]{} - Entregas recibidas: {} - No llegaron antes del cierre: {}",
+            COLOR_YELLOW,
+            COLOR_RESET,
+            delivery_records.len(),
+            self.pending_deliveries
+                .lock()
+                .expect("Failed to lock pending_deliveries")
+                .len()
+        ));
+        for record in self
+            .purchase_records
+            .lock()
+            .expect("Failed to lock purchase_records")
+            .iter()
+        {
+            lines.push(format!(
+                "{}[Estadísticas]{} - {}",
+                COLOR_YELLOW, COLOR_RESET, record
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Obtains the deliveries of alerts to each sink
+    fn obtain_sink_stats(&self) -> String {
+        self.alert_notifier
+            .stats()
+            .iter()
+            .map(|(sink, stats)| {
+                format!(
+                    "{}[Estadísticas]{} - Notificaciones a {}: {}",
+                    COLOR_YELLOW, COLOR_RESET, sink, stats
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Obtains the wakeups of the threads waiting on each container, for each signal
+    fn obtain_wakeup_stats(&self) -> String {
+        let mut lines = Vec::new();
+        for kind in ContainerKind::ALL {
+            let container = self.container(kind);
+            let wakeups: Vec<String> = Signal::ALL
+                .iter()
+                .map(|signal| format!("{}: {}", signal, container.get_wakeups(*signal)))
+                .collect();
+            lines.push(format!(
+                "{}[Estadísticas]{} - Despertares en {}: {}",
+                COLOR_YELLOW,
+                COLOR_RESET,
+                kind,
+                wakeups.join(" || ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// Obtains every change in the size of the dispenser pool made by the autoscaler
+    fn obtain_scale_events(&self) -> String {
+        let scale_events = self
+            .scale_events
+            .lock()
+            .expect("Failed to lock scale_events");
+        let mut report = format!(
+            "{}[Estadísticas]{} - Eventos de escalado: {}",
+            COLOR_YELLOW,
+            COLOR_RESET,
+            scale_events.len()
+        );
+        for event in scale_events.iter() {
+            report.push_str(&format!("\n    {}", event));
+        }
+        report
+    }
+
+    /// Obtains the work done by each dispenser
+    fn obtain_dispenser_stats(&self) -> String {
+        let dispenser_stats = self
+            .dispenser_stats
+            .lock()
+            .expect("Failed to lock dispenser_stats");
+        let empty_stats = DispenserStats::new();
+        self.dispensers
+            .lock()
+            .expect("Failed to lock dispensers")
+            .iter()
+            .map(|dispenser| {
+                format!(
+                    "{}[Estadísticas]{} - {}: {}",
+                    COLOR_YELLOW,
+                    COLOR_RESET,
+                    dispenser,
+                    dispenser_stats
+                        .get(&dispenser.get_id())
+                        .unwrap_or(&empty_stats)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Obtains the drinks served by name, if any was ordered by name
+    fn obtain_drink_stats(&self) -> Option<String> {
+        let drink_stats = self.drink_stats.lock().expect("Failed to lock drink_stats");
+        if drink_stats.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{}[Estadísticas]{} - {}",
+            COLOR_YELLOW, COLOR_RESET, drink_stats
+        ))
+    }
+
+    /// Obtains the orders of each named input, if any of them had orders
+    fn obtain_source_stats(&self) -> Option<String> {
+        let source_stats = self
+            .source_stats
+            .lock()
+            .expect("Failed to lock source_stats");
+        if source_stats.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{}[Estadísticas]{} - {}",
+            COLOR_YELLOW, COLOR_RESET, source_stats
+        ))
+    }
+
+    /// Obtains the drinks served with substitutes and the stock left of each of them, if there
+    /// are substitutes or any order was discarded for lack of an ingredient
+    fn obtain_substitution_stats(&self) -> Option<String> {
+        let substitution_stats = self
+            .substitution_stats
+            .lock()
+            .expect("Failed to lock substitution_stats");
+        if self.substitutes.is_empty() && substitution_stats.is_empty() {
+            return None;
+        }
+        let mut report = format!(
+            "{}[Estadísticas]{} - {}",
+            COLOR_YELLOW, COLOR_RESET, substitution_stats
+        );
+        if !self.substitutes.is_empty() {
+            let stock: Vec<String> = self
+                .substitutes
+                .stock()
+                .iter()
+                .map(|(name, amount)| format!("{}: {}", name, amount))
+                .collect();
+            report.push_str(&format!(" || Sustitutos restantes: {}", stock.join(" - ")));
+        }
+        Some(report)
+    }
+
+    /// Obtains the rows of the input that were not valid orders, if there were any
+    fn obtain_reject_stats(&self) -> Option<String> {
+        let reject_stats = self.order_reader.get_reject_stats();
+        if reject_stats.total() == 0 {
+            return None;
+        }
+        let mut report = format!(
+            "{}[Estadísticas]{} - {}",
+            COLOR_YELLOW, COLOR_RESET, reject_stats
+        );
+        if reject_stats.get_quarantined() > 0 {
+            report.push_str(&format!(
+                " (en {})",
+                self.order_reader.get_rejects_path().display()
+            ));
+        }
+        Some(report)
+    }
+
+    /// Obtains the crashes of the dispensers and the container locks recovered after them, if
+    /// there were any
+    fn obtain_crash_stats(&self) -> Option<String> {
+        let crash_stats = self.crash_stats.lock().expect("Failed to lock crash_stats");
+        let poison_recoveries: u64 = ContainerKind::ALL
+            .iter()
+            .map(|kind| self.container(*kind).get_poison_recoveries())
+            .sum();
+        if !crash_stats.has_crashes() && poison_recoveries == 0 {
+            return None;
+        }
+        Some(format!(
+            "{}[Estadísticas]{} - {} - Locks de contenedores recuperados: {}",
+            COLOR_YELLOW, COLOR_RESET, crash_stats, poison_recoveries
+        ))
+    }
+
+    /// Informs about the orders left in the queue because no dispenser was able to make them
+    fn report_unserved_orders(&self) -> Vec<String> {
+        self.dispatcher
+            .drain()
+            .into_iter()
+            .filter_map(|message| match message {
+                Message::Job(order) => Some(format!(
+                    "{}[Estadísticas]{} - Ningún dispenser puede preparar el pedido: {}",
+                    COLOR_YELLOW, COLOR_RESET, order
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Saves the orders nobody started to prepare, so they can be served in a later run
+    fn save_unstarted_orders(&self) -> String {
+        self.set_aside_unstarted_orders();
+        let unstarted_orders = self
+            .unstarted_orders
+            .lock()
+            .expect("Failed to lock unstarted_orders");
+        if unstarted_orders.is_empty() {
+            return format!(
+                "{}[Estadísticas]{} - No quedaron pedidos sin empezar",
+                COLOR_YELLOW, COLOR_RESET
+            );
+        }
+        match persist_orders(&unstarted_orders, &self.unstarted_orders_path) {
+            Ok(()) => format!(
+                "{}[Estadísticas]{} - Se guardaron {} pedidos sin empezar en {}",
+                COLOR_YELLOW,
+                COLOR_RESET,
+                unstarted_orders.len(),
+                self.unstarted_orders_path.display()
+            ),
+            Err(error) => format!(
+                "{}[Estadísticas]{} - No se pudieron guardar {} pedidos sin empezar en {}: {}",
+                COLOR_RED,
+                COLOR_RESET,
+                unstarted_orders.len(),
+                self.unstarted_orders_path.display(),
+                error
+            ),
+        }
+    }
+
+    /// Stops the machine because a termination signal arrived
+    /// A second signal exits right away, even if the machine was already stopping for another
+    /// reason
+    pub fn interrupt(self: &Arc<Self>, signal: i32) {
+        if self.is_stopping() {
+            println!(
+                "{}[Apagado]{} - Se recibió {} nuevamente, saliendo sin esperar",
+                COLOR_RED,
+                COLOR_RESET,
+                signal_name(signal)
+            );
+            process::exit(
+                Outcome::Stopped {
+                    reason: StopReason::Signal(signal),
+                    drained: false,
+                }
+                .exit_code(),
+            );
+        }
+        self.stop(StopReason::Signal(signal));
+    }
+
+    /// Stops the machine because one of its threads failed
+    /// Only the first failure stops the machine, the later ones are just informed
+    fn fail(self: &Arc<Self>, actor: &str, error: String) {
+        self.stop(StopReason::Failure {
+            actor: actor.to_string(),
+            error,
+        });
+    }
+
+    /// Returns whether the machine is stopping before serving every order
+    fn is_stopping(&self) -> bool {
+        self.stop_reason
+            .lock()
+            .expect("Failed to lock stop_reason")
+            .is_some()
+    }
+
+    /// Stops taking orders for the given reason
+    /// The orders waiting in the queue are set aside, and the dispensers are asked to stop once
+    /// they finish the drink they are preparing
+    fn stop(self: &Arc<Self>, reason: StopReason) {
+        {
+            let mut stop_reason = self.stop_reason.lock().expect("Failed to lock stop_reason");
+            if stop_reason.is_some() {
+                println!("{}[Apagado]{} - También {}", COLOR_RED, COLOR_RESET, reason);
+                return;
+            }
+            println!(
+                "{}[Apagado]{} - Se detiene la máquina porque {}. No se toman más pedidos y se espera hasta {:.1}s a que terminen las bebidas en preparación",
+                COLOR_RED,
+                COLOR_RESET,
+                reason,
+                self.drain_timeout.as_secs_f64()
+            );
+            *stop_reason = Some((reason, Instant::now() + self.drain_timeout));
+        }
+        self.intake_closed.store(true, Ordering::Relaxed);
+        self.set_aside_unstarted_orders();
+        self.send_shutdown_message();
+    }
+
+    /// Takes the orders waiting in the queue out of it, keeping the other messages
+    fn set_aside_unstarted_orders(&self) {
+        let mut unstarted_orders = self
+            .unstarted_orders
+            .lock()
+            .expect("Failed to lock unstarted_orders");
+        for message in self.dispatcher.drain() {
+            match message {
+                Message::Job(order) => {
+                    self.return_cup(&order);
+                    unstarted_orders.push(order);
+                }
+                message => self.dispatcher.push(message),
+            }
+        }
+    }
+
+    /// Returns the mean and tail latencies of the orders served so far
+    pub fn latency_summary(&self) -> LatencySummary {
+        self.latency_stats
+            .lock()
+            .expect("Failed to lock latency_stats")
+            .summary()
+    }
+
+    /// Reads the orders from the given inputs
+    /// This method will start a thread per input that will read its orders
+    /// Once every input runs out of orders, the dispensers are told to stop
+    fn read_orders(self: &Arc<Self>, inputs: Vec<OrderInput>) -> Vec<JoinHandle<()>> {
+        self.open_inputs.store(inputs.len(), Ordering::Relaxed);
+        if inputs.is_empty() {
+            self.send_shutdown_message();
+        }
+        inputs
+            .into_iter()
+            .enumerate()
+            .map(|(position, input)| {
+                let actor = reader_name(input.name.as_deref());
+                let failed_actor = format!("el {}", actor.to_lowercase());
+                self.spawn_actor(failed_actor.clone(), move |coffee_machine| {
+                    if let Err(error) = coffee_machine
+                        .order_reader
+                        .read_from(input, position as u64)
+                    {
+                        coffee_machine.fail(&failed_actor, error);
+                        return;
+                    }
+                    println!(
+                        "{}[{}]{} - No hay más pedidos para leer",
+                        COLOR_BLUE, actor, COLOR_RESET
+                    );
+                    if coffee_machine.open_inputs.fetch_sub(1, Ordering::Relaxed) == 1 {
+                        coffee_machine.send_shutdown_message();
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Sends a shutdown message to the blocking queue, in order to notify the dispensers
+    /// that they should stop
+    /// From this moment on, no dispenser can join or leave the pool
+    /// The message is only sent once, even if both the reader and a stop ask for it
+    fn send_shutdown_message(self: &Arc<Self>) {
+        let active_dispensers = self
+            .active_dispensers
+            .lock()
+            .expect("Failed to lock active_dispensers");
+        if self.pool_closed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        // Para finalizar el programa y hacer un shutdown, debo comunicarle a los dispensers que ya no hay más pedidos.
+        for _ in 0..*active_dispensers {
+            self.dispatcher.push(Message::Shutdown);
+        }
+    }
+
+    /// Prepares the orders
+    /// This method will start a number of threads that will prepare the orders
+    /// There is one thread per dispenser given in the configuration
+    fn prepare_orders(self: &Arc<Self>) {
+        for spec in &self.initial_dispensers {
+            self.add_dispenser(*spec);
+        }
+    }
+
+    /// Prepares a drink
+    /// This method will prepare a drink, using the resources from the containers
+    /// If there is not enough resources, the thread will refill the containers accordingly
+    /// Only the orders the dispenser is able to make are taken
+    fn make_drink(self: &Arc<Self>, dispenser: Dispenser) {
+        let n_dispenser = dispenser.get_id();
+        loop {
+            let order = self.dispatcher.pop(
+                &dispenser,
+                self.scheduling_policy.as_ref(),
+                &self.refill_status,
+            );
+            match order {
+                Message::Job(order) if order.is_expired() => {
+                    println!(
+                        "{}[Dispenser {}]{} - El cliente se fue antes de ser atendido, descartando pedido: {}",
+                        COLOR_GREEN, n_dispenser, COLOR_RESET, order
+                    );
+                    self.sla_stats
+                        .lock()
+                        .expect("Failed to lock sla_stats")
+                        .record_abandoned();
+                    self.release_substitutes(&order);
+                    self.return_cup(&order);
+                }
+                Message::Job(mut order) => {
+                    println!(
+                        "{}[Dispenser {}]{} - Recibió pedido: {}",
+                        COLOR_GREEN, n_dispenser, COLOR_RESET, order
+                    );
+                    if !self.reserve_ingredients(&mut order, &dispenser) {
+                        self.return_cup(&order);
+                        continue;
+                    }
+                    if !order.has_cup() {
+                        if !self.take_cup(&order, &dispenser) {
+                            self.release_ingredients(&order);
+                            self.release_substitutes(&order);
+                            continue;
+                        }
+                        order.mark_cup_taken();
+                    }
+                    self.start_preparing(n_dispenser, &order);
+                    self.prepare_drink(order, &dispenser);
+                    self.in_flight
+                        .lock()
+                        .expect("Failed to lock in_flight")
+                        .remove(&n_dispenser);
+                    println!(
+                        "{}[Dispenser {}]{} - Terminó de preparar bebida ✓",
+                        COLOR_GREEN, n_dispenser, COLOR_RESET
+                    );
+                }
+                Message::Retire(_) => {
+                    println!(
+                        "{}[Dispenser {}]{} - Retirando dispenser del pool",
+                        COLOR_GREEN, n_dispenser, COLOR_RESET
+                    );
+                    self.dispenser_stats
+                        .lock()
+                        .expect("Failed to lock dispenser_stats")
+                        .entry(n_dispenser)
+                        .or_insert_with(DispenserStats::new)
+                        .mark_retired();
+                    self.dispatcher.unregister(&dispenser);
+                    break;
+                }
+                Message::Shutdown => {
+                    println!(
+                        "{}[Dispenser {}]{} - No hay pedidos, apagando dispenser",
+                        COLOR_GREEN, n_dispenser, COLOR_RESET
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sets aside the coffee and milk the order needs, counting the raw material they are made
+    /// from and what the drinks being prepared already set aside
+    /// If there is not enough of an ingredient, it is replaced by the first configured substitute
+    /// the customer accepts, and if there is none the order is discarded
+    /// Returns True if every ingredient was set aside
+    fn reserve_ingredients(&self, order: &mut Order, dispenser: &Dispenser) -> bool {
+        let mut taken = Vec::new();
+        for ingredient in [Resource::Coffee, Resource::Milk] {
+            let amount = order.get_amount(ingredient);
+            if amount == 0 || order.get_substitute(ingredient).is_some() {
+                continue;
+            }
+            if self.reserve_ingredient(ingredient, amount) {
+                taken.push(ingredient);
+                continue;
+            }
+            let reservation =
+                self.substitutes
+                    .reserve(ingredient, amount, &order.get_details().substitutes);
+            if let Reservation::Reserved(name) = reservation {
+                println!(
+                    "{}[Dispenser {}]{} - No hay suficiente {} para el pedido, se usará {}",
+                    COLOR_GREEN,
+                    dispenser.get_id(),
+                    COLOR_RESET,
+                    ingredient.field_name(),
+                    name
+                );
+                order.substitute(ingredient, name);
+                continue;
+            }
+            for ingredient in taken {
+                self.release_ingredient(ingredient, order.get_amount(ingredient));
+            }
+            self.release_substitutes(order);
+            println!(
+                "{}[Dispenser {}]{} - No hay suficiente {} ni un sustituto aceptado, descartando pedido: {}",
+                COLOR_GREEN,
+                dispenser.get_id(),
+                COLOR_RESET,
+                ingredient.field_name(),
+                order
+            );
+            self.substitution_stats
+                .lock()
+                .expect("Failed to lock substitution_stats")
+                .record_discarded(reservation == Reservation::Declined);
+            return false;
+        }
+        true
+    }
+
+    /// Sets aside the amount of the ingredient if what is left in the machine, counting the raw
+    /// material it is made from, covers it. Water never runs out
+    /// The containers are locked before `reserved`, as when the ingredient is served, so the
+    /// amount served and released is never counted twice
+    /// Returns True if it was set aside
+    fn reserve_ingredient(&self, ingredient: Resource, amount: u64) -> bool {
+        let (container, raw_container) = match ingredient {
+            Resource::Coffee => (
+                &self.ground_coffee_beans_container,
+                &self.coffee_beans_to_grind_container,
+            ),
+            Resource::Milk => (&self.milk_foam_container, &self.cold_milk_container),
+            Resource::Water => return true,
+        };
+        let container = container.lock();
+        let raw_container = raw_container.lock();
+        let mut reserved = self.reserved.lock().expect("Failed to lock reserved");
+        let stock = container.get_current_amount() + raw_container.get_current_amount();
+        if stock.saturating_sub(reserved[ingredient as usize]) < amount {
+            return false;
+        }
+        reserved[ingredient as usize] += amount;
+        true
+    }
+
+    /// Gives back the amount of the ingredient set aside for an order, once it is served or if
+    /// the order is not prepared
+    fn release_ingredient(&self, ingredient: Resource, amount: u64) {
+        let mut reserved = self.reserved.lock().expect("Failed to lock reserved");
+        reserved[ingredient as usize] = reserved[ingredient as usize].saturating_sub(amount);
+    }
+
+    /// Gives back the amount of the ingredient set aside for the order the dispenser is
+    /// preparing, once it is served, so that it is not given back again if the dispenser crashes
+    fn release_served(&self, ingredient: Resource, amount: u64, dispenser: &Dispenser) {
+        if let Some(in_flight) = self
+            .in_flight
+            .lock()
+            .expect("Failed to lock in_flight")
+            .get_mut(&dispenser.get_id())
+        {
+            in_flight.reserved[ingredient as usize] = 0;
+        }
+        self.release_ingredient(ingredient, amount);
+    }
+
+    /// Returns the amount of each ingredient set aside for the order, except the substitutes
+    fn reserved_amounts(order: &Order) -> [u64; 3] {
+        let mut reserved = [0; 3];
+        for ingredient in [Resource::Coffee, Resource::Milk] {
+            if order.get_substitute(ingredient).is_none() {
+                reserved[ingredient as usize] = order.get_amount(ingredient);
+            }
+        }
+        reserved
+    }
+
+    /// Gives back the coffee and milk set aside for an order that was not prepared, except the
+    /// substitutes
+    fn release_ingredients(&self, order: &Order) {
+        let reserved = Self::reserved_amounts(order);
+        for ingredient in Resource::ALL {
+            self.release_ingredient(ingredient, reserved[ingredient as usize]);
+        }
+    }
+
+    /// Records that the dispenser starts preparing the order, so that the supervisor can
+    /// recover it if the dispenser crashes
+    fn start_preparing(&self, n_dispenser: u64, order: &Order) {
+        self.in_flight
+            .lock()
+            .expect("Failed to lock in_flight")
+            .insert(
+                n_dispenser,
+                InFlightOrder {
+                    order: order.clone(),
+                    reserved: Self::reserved_amounts(order),
+                },
+            );
+    }
+
+    /// Gives back the substitutes set aside for an order that was not prepared
+    fn release_substitutes(&self, order: &Order) {
+        for (ingredient, name) in order.get_substitutions() {
+            self.substitutes
+                .release(name, order.get_amount(*ingredient));
+        }
+    }
+
+    /// Prepares a drink
+    /// This method will prepare a drink, using the resources from the containers
+    fn prepare_drink(&self, order: Order, dispenser: &Dispenser) {
+        let preparation_start = Instant::now();
+        for step in order.get_steps() {
+            self.apply_step(step, &order, dispenser);
+        }
+        for extra in order.get_extras() {
+            println!(
+                "{}[Dispenser {}]{} - Agregando {}",
+                COLOR_GREEN,
+                dispenser.get_id(),
+                COLOR_RESET,
+                extra
+            );
+        }
+
+        self.increase_drinks_prepared();
+        self.drink_stats
+            .lock()
+            .expect("Failed to lock drink_stats")
+            .record(&order);
+        self.source_stats
+            .lock()
+            .expect("Failed to lock source_stats")
+            .record_served(&order);
+        self.substitution_stats
+            .lock()
+            .expect("Failed to lock substitution_stats")
+            .record(&order);
+        self.size_stats
+            .lock()
+            .expect("Failed to lock size_stats")
+            .record_sold(order.get_size());
+        self.latency_stats
+            .lock()
+            .expect("Failed to lock latency_stats")
+            .record(order.get_arrival_time().elapsed());
+        self.dispenser_stats
+            .lock()
+            .expect("Failed to lock dispenser_stats")
+            .entry(dispenser.get_id())
+            .or_insert_with(DispenserStats::new)
+            .record(preparation_start.elapsed());
+        self.record_sla(&order, dispenser.get_id());
+    }
+
+    /// Applies the ingredients of a step of the recipe. If there are more than one, they are
+    /// applied at the same time
+    fn apply_step(&self, step: &[Resource], order: &Order, dispenser: &Dispenser) {
+        let step_start = Instant::now();
+        let elapsed: Vec<Duration> = if let [ingredient] = step {
+            vec![self.apply_ingredient(*ingredient, order, dispenser)]
+        } else {
+            let names: Vec<&str> = step.iter().map(Resource::field_name).collect();
+            println!(
+                "{}[Dispenser {}]{} - Aplicando a la vez {}",
+                COLOR_GREEN,
+                dispenser.get_id(),
+                COLOR_RESET,
+                names.join(", ")
+            );
+            thread::scope(|scope| {
+                let handles: Vec<_> = step
+                    .iter()
+                    .map(|ingredient| {
+                        scope.spawn(|| self.apply_ingredient(*ingredient, order, dispenser))
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("Failed to apply ingredient"))
+                    .collect()
+            })
+        };
+        let mut step_stats = self.step_stats.lock().expect("Failed to lock step_stats");
+        for (ingredient, elapsed) in step.iter().zip(&elapsed) {
+            step_stats.record(*ingredient, *elapsed);
+        }
+        if step.len() > 1 {
+            step_stats.record_parallel(step_start.elapsed(), elapsed.iter().sum());
+        }
+    }
+
+    /// Applies one ingredient of the order, if it needs any.
+    /// Returns how long it took
+    fn apply_ingredient(
+        &self,
+        ingredient: Resource,
+        order: &Order,
+        dispenser: &Dispenser,
+    ) -> Duration {
+        let start = Instant::now();
+        if let Some(name) = order.get_substitute(ingredient) {
+            self.serve_substitute(ingredient, name, order.get_amount(ingredient), dispenser);
+            return start.elapsed();
+        }
+        match ingredient {
+            Resource::Coffee if order.requires_coffee() => {
+                self.serve_coffee(order.get_coffee(), dispenser)
+            }
+            Resource::Milk if order.requires_milk() => self.serve_milk(order.get_milk(), dispenser),
+            Resource::Water if order.requires_water() => {
+                self.serve_water(order.get_water(), dispenser)
+            }
+            _ => {}
+        }
+        start.elapsed()
+    }
+
+    /// Records whether the order was served within its deadline, if it has one
+    fn record_sla(&self, order: &Order, n_dispenser: u64) {
+        if order.deadline().is_none() {
+            return;
+        }
+        let mut sla_stats = self.sla_stats.lock().expect("Failed to lock sla_stats");
+        if order.is_expired() {
+            println!(
+                "{}[Dispenser {}]{} - La bebida se terminó fuera de término",
+                COLOR_GREEN, n_dispenser, COLOR_RESET
+            );
+            sla_stats.record_late(order);
+        } else {
+            sla_stats.record_on_time();
+        }
+    }
+
+    /// Increases the number of drinks prepared
+    fn increase_drinks_prepared(&self) {
+        let mut total_drinks = self
+            .total_drinks_prepared
+            .lock()
+            .expect("Failed to lock total_drinks");
+        *total_drinks += 1;
+    }
+
+    /// Serves a substitute instead of an ingredient that ran out. Its stock was already taken
+    /// when the order was reserved
+    fn serve_substitute(
+        &self,
+        ingredient: Resource,
+        name: &str,
+        amount: u64,
+        dispenser: &Dispenser,
+    ) {
+        let n_dispenser = dispenser.get_id();
+        println!(
+            "{}[Dispenser {}]{} - Aplicando {} de {} en lugar de {}",
+            COLOR_GREEN,
+            n_dispenser,
+            COLOR_RESET,
+            amount,
+            name,
+            ingredient.field_name()
+        );
+        thread::sleep(dispenser.application_time(BASE_TIME_RESOURCE_APPLICATION * amount));
+        println!(
+            "{}[Dispenser {}]{} - Terminó de aplicar {}",
+            COLOR_GREEN, n_dispenser, COLOR_RESET, name
+        );
+    }
+
+    /// Serves water to the drink
+    fn serve_water(&self, water_amount: &u64, dispenser: &Dispenser) {
+        let n_dispenser = dispenser.get_id();
+        println!(
+            "{}[Dispenser {}]{} - Aplicando agua",
+            COLOR_GREEN, n_dispenser, COLOR_RESET
+        );
+        thread::sleep(dispenser.application_time(BASE_TIME_RESOURCE_APPLICATION * water_amount));
+        println!(
+            "{}[Dispenser {}]{} - Terminó de aplicar agua",
+            COLOR_GREEN, n_dispenser, COLOR_RESET
+        );
+    }
+
+    /// Serves milk to the drink
+    /// This method will serve milk to the drink, if there is enough milk in the container
+    /// If there is not enough milk, the method will refill the container
+    fn serve_milk(&self, milk_amount: &u64, dispenser: &Dispenser) {
+        let n_dispenser = dispenser.get_id();
+        let mut milk_foam = self.milk_foam_container.lock();
+        if !milk_foam.has_enough(milk_amount) {
+            println!(
+                "{}[Dispenser {}]{} - No hay suficiente {} leche espumada para preparar la bebida",
+                COLOR_GREEN, n_dispenser, COLOR_RESET, milk_amount
+            );
+            let mut cold_milk = self.cold_milk_container.lock();
+            self.refill_status.set_milk_refilling(true);
+            refill_milk(
+                &mut milk_foam,
+                &((*milk_amount as f64 * 1.5) as u64),
+                &mut cold_milk,
+            );
+            self.refill_status.set_milk_refilling(false);
+            self.cold_milk_container.notify_consumption(&cold_milk);
+            self.milk_foam_container.notify_refill(&milk_foam);
+        }
+        println!(
+            "{}[Dispenser {}]{} - Aplicando {} de leche espumada",
+            COLOR_GREEN, n_dispenser, COLOR_RESET, milk_amount
+        );
+        thread::sleep(dispenser.application_time(BASE_TIME_RESOURCE_APPLICATION * milk_amount));
+        milk_foam.subtract(milk_amount);
+        self.release_served(Resource::Milk, *milk_amount, dispenser);
+        println!(
+            "{}[Dispenser {}]{} - Terminó de aplicar leche espumada",
+            COLOR_GREEN, n_dispenser, COLOR_RESET
+        );
+        self.milk_foam_container.notify_consumption(&milk_foam);
+    }
+
+    /// Serves coffee to the drink
+    /// This method will serve coffee to the drink, if there is enough coffee in the container
+    /// If there is not enough coffee, the method will refill the container
+    fn serve_coffee(&self, coffee_amount: &u64, dispenser: &Dispenser) {
+        let n_dispenser = dispenser.get_id();
+        let mut ground_coffee_beans = self.ground_coffee_beans_container.lock();
+        if !ground_coffee_beans.has_enough(coffee_amount) {
+            println!(
+                "{}[Dispenser {}]{} - No hay suficientes {} granos de café para preparar la bebida",
+                COLOR_GREEN, n_dispenser, COLOR_RESET, coffee_amount
+            );
+            let mut coffee_beans_to_grind = self.coffee_beans_to_grind_container.lock();
+            self.refill_status.set_coffee_refilling(true);
+            refill_coffee(
+                &mut ground_coffee_beans,
+                &((*coffee_amount as f64 * 1.5) as u64),
+                &mut coffee_beans_to_grind,
+            );
+            self.refill_status.set_coffee_refilling(false);
+            self.coffee_beans_to_grind_container
+                .notify_consumption(&coffee_beans_to_grind);
+            self.ground_coffee_beans_container
+                .notify_refill(&ground_coffee_beans);
+        }
+        println!(
+            "{}[Dispenser {}]{} - Aplicando {} granos de café",
+            COLOR_GREEN, n_dispenser, COLOR_RESET, coffee_amount
+        );
+        thread::sleep(dispenser.application_time(BASE_TIME_RESOURCE_APPLICATION * coffee_amount));
+        ground_coffee_beans.subtract(coffee_amount);
+        self.release_served(Resource::Coffee, *coffee_amount, dispenser);
+        println!(
+            "{}[Dispenser {}]{} - Terminó de aplicar granos de café",
+            COLOR_GREEN, n_dispenser, COLOR_RESET
+        );
+        self.ground_coffee_beans_container
+            .notify_consumption(&ground_coffee_beans);
+    }
+
+    /// Refills the milk container
+    /// This method will refill the milk container, using the cold milk container
+    fn transform_milk(&self) {
+        loop {
+            let mut milk_foam = self.milk_foam_container.wait_while(
+                Signal::RefillNeeded,
+                self.milk_foam_container.lock(),
+                |milk_foam| milk_foam.has_any() && !self.should_shutdown.load(Ordering::Relaxed),
+            );
+            if self.should_shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            let mut cold_milk = self.cold_milk_container.lock();
+            println!(
+                "{}[Refill de leche espumada]{} - La leche espumada se ha agotado",
+                COLOR_MAGENTA, COLOR_RESET,
+            );
+            if !cold_milk.has_any() {
+                println!(
+                    "{}[Refill de leche espumada]{} - No queda leche fría, esperando una entrega",
+                    COLOR_MAGENTA, COLOR_RESET,
+                );
+                drop(milk_foam);
+                self.wait_for_delivery(&self.cold_milk_container, cold_milk);
+                continue;
+            }
+            self.refill_status.set_milk_refilling(true);
+            refill_milk(&mut milk_foam, &MILK_TO_REFILL, &mut cold_milk);
+            self.refill_status.set_milk_refilling(false);
+            self.cold_milk_container.notify_consumption(&cold_milk);
+            self.milk_foam_container.notify_refill(&milk_foam);
+        }
+        println!(
+            "{}[Refill de leche espumada]{} - Apagando refill de leche espumada",
+            COLOR_MAGENTA, COLOR_RESET
+        );
+    }
+
+    /// Refills the coffee container
+    /// This method will refill the coffee container, using the coffee beans to grind container
+    fn transform_coffee(&self) {
+        loop {
+            let mut ground_coffee_beans = self.ground_coffee_beans_container.wait_while(
+                Signal::RefillNeeded,
+                self.ground_coffee_beans_container.lock(),
+                |ground_coffee_beans| {
+                    ground_coffee_beans.has_any() && !self.should_shutdown.load(Ordering::Relaxed)
+                },
+            );
+            if self.should_shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+            println!(
+                "{}[Refill de granos de café]{} - Los granos de café molido se han agotado",
+                COLOR_CYAN, COLOR_RESET,
+            );
+            let mut coffee_beans_to_grind = self.coffee_beans_to_grind_container.lock();
+            if !coffee_beans_to_grind.has_any() {
+                println!(
+                    "{}[Refill de granos de café]{} - No quedan granos para moler, esperando una entrega",
+                    COLOR_CYAN, COLOR_RESET,
+                );
+                drop(ground_coffee_beans);
+                self.wait_for_delivery(
+                    &self.coffee_beans_to_grind_container,
+                    coffee_beans_to_grind,
+                );
+                continue;
+            }
+            self.refill_status.set_coffee_refilling(true);
+            refill_coffee(
+                &mut ground_coffee_beans,
+                &COFFEE_TO_REFILL,
+                &mut coffee_beans_to_grind,
+            );
+            self.refill_status.set_coffee_refilling(false);
+            self.coffee_beans_to_grind_container
+                .notify_consumption(&coffee_beans_to_grind);
+            self.ground_coffee_beans_container
+                .notify_refill(&ground_coffee_beans);
+        }
+        println!(
+            "{}[Refill de café]{} - Apagando refill de granos de café",
+            COLOR_CYAN, COLOR_RESET
+        );
+    }
+
+    /// Spawns a thread that will refill the milk container
+    fn refill_milk(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(
+            String::from("el refill de leche espumada"),
+            |coffee_machine| coffee_machine.transform_milk(),
+        )
+    }
+
+    /// Spawns a thread that will refill the coffee container
+    fn refill_coffee(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(String::from("el refill de café"), |coffee_machine| {
+            coffee_machine.transform_coffee()
+        })
+    }
+
+    /// Spawns a thread that will inform about the statistic of the coffee machine
+    fn inform_system(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(
+            String::from("el informe de estadísticas"),
+            |coffee_machine| coffee_machine.inform_stats(),
+        )
+    }
+
+    /// Spawns a thread of the machine, named `actor` in the reports
+    /// If the thread panics, the machine stops instead of waiting for it forever
+    fn spawn_actor(
+        self: &Arc<Self>,
+        actor: String,
+        body: impl FnOnce(&Arc<Self>) + Send + 'static,
+    ) -> JoinHandle<()> {
+        let coffee_machine_clone = self.clone();
+        thread::spawn(move || {
+            if let Err(payload) =
+                panic::catch_unwind(AssertUnwindSafe(|| body(&coffee_machine_clone)))
+            {
+                coffee_machine_clone.fail(&actor, panic_message(payload.as_ref()));
+            }
+        })
+    }
+
+    /// Informs about the statistic of the coffee machine
+    fn inform_stats(&self) {
+        while !self.should_shutdown.load(Ordering::Relaxed) {
+            let report = self.obtain_stats();
+            println!("{}", report);
+            thread::sleep(Duration::from_secs(STATS_UPDATE_INTERVAL));
+        }
+        println!(
+            "{}[Estadísticas]{} - Apagando informe del sistema",
+            COLOR_YELLOW, COLOR_RESET
+        );
+    }
+
+    #[allow(clippy::format_push_string)]
+    /// Obtains the statistic of the coffee machine
+    /// This method will return a string with the statistic of the coffee machine
+    /// It will return the amount of coffee, coffee beans, milk and milk foam that has been used
+    fn obtain_stats(&self) -> String {
+        let mut report = String::from("\x1b[33m[Estadísticas]\x1b[0m - ");
+        {
+            let total_drinks = self
+                .total_drinks_prepared
+                .lock()
+                .expect("Failed to lock total_drinks");
+            report.push_str(&format!(
+                "Total de bebidas preparadas: {} || ",
+                total_drinks
+            ));
+        }
+        {
+            let active_dispensers = self
+                .active_dispensers
+                .lock()
+                .expect("Failed to lock active_dispensers");
+            report.push_str(&format!("Dispensers activos: {} || ", active_dispensers));
+        }
+        {
+            let ground_coffee_beans = self.ground_coffee_beans_container.lock();
+            report.push_str(&format!(
+                "Café molido actualmente: {} - Consumido: {} - Agotamiento estimado: {} || ",
+                ground_coffee_beans.get_current_amount(),
+                ground_coffee_beans.get_amount_used(),
+                self.depletion_eta(ContainerKind::GroundCoffee)
+            ));
+        }
+        {
+            let coffee_beans_to_grind = self.coffee_beans_to_grind_container.lock();
+            report.push_str(&format!(
+                "Café en grano actualmente: {} - Consumido: {} - Agotamiento estimado: {} || ",
+                coffee_beans_to_grind.get_current_amount(),
+                coffee_beans_to_grind.get_amount_used(),
+                self.depletion_eta(ContainerKind::CoffeeBeans)
+            ));
+        }
+        {
+            let cold_milk = self.cold_milk_container.lock();
+            report.push_str(&format!(
+                "Leche fría actualmente: {} - Consumida: {} - Agotamiento estimado: {} || ",
+                cold_milk.get_current_amount(),
+                cold_milk.get_amount_used(),
+                self.depletion_eta(ContainerKind::ColdMilk)
+            ));
+        }
+        {
+            let milk_foam = self.milk_foam_container.lock();
+            report.push_str(&format!(
+                "Leche espumada actualmente: {} - Consumida: {} - Agotamiento estimado: {} || ",
+                milk_foam.get_current_amount(),
+                milk_foam.get_amount_used(),
+                self.depletion_eta(ContainerKind::MilkFoam)
+            ));
+        }
+        let cups: Vec<String> = Size::ALL
+            .iter()
+            .map(|size| {
+                format!(
+                    "{}: {}",
+                    size,
+                    self.container(size.cups()).lock().get_current_amount()
+                )
+            })
+            .collect();
+        report.push_str(&format!("Vasos actualmente: {} ", cups.join(" - ")));
+        report
+    }
+}
+
+/// Removes the color codes of a report, so it can be written to a file
+fn strip_colors(report: &str) -> String {
+    [
+        COLOR_RED,
+        COLOR_GREEN,
+        COLOR_YELLOW,
+        COLOR_BLUE,
+        COLOR_MAGENTA,
+        COLOR_CYAN,
+        COLOR_RESET,
+    ]
+    .iter()
+    .fold(report.to_string(), |report, color| {
+        report.replace(color, "")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispenser::Capabilities;
+    use crate::menu::{Amounts, Recipe};
+    use crate::order::OrderDetails;
+
+    /// Creates a dispenser that can make every drink.
+    pub(super) fn dispenser() -> Dispenser {
+        Dispenser::new(1, DispenserSpec::new(Capabilities::all(), 100.0))
+    }
+
+    /// Creates an order of water of the given size.
+    pub(super) fn order(size: Size, patience: Option<Duration>) -> Order {
+        let amounts = Amounts {
+            coffee: 0,
+            milk: 0,
+            water: 1,
+        };
+        Order::from_recipe(Recipe::new(amounts), size, patience)
+    }
+
+    #[test]
+    fn each_drink_served_is_sold_in_its_size() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        let dispenser = dispenser();
+        for size in [Size::Small, Size::Large, Size::Large] {
+            coffee_machine.prepare_drink(order(size, None), &dispenser);
+        }
+
+        let size_stats = coffee_machine.size_stats.lock().unwrap().to_string();
+        assert!(size_stats.starts_with("Ventas por tamaño: chico: 1 - mediano: 0 - grande: 2"));
+    }
+
+    /// Creates a coffee machine with the given stock of oat milk as a substitute for milk.
+    fn with_oat_milk(stock: u64) -> Arc<CoffeeMachine> {
+        let config = Config {
+            substitutes: vec![format!("milk=avena:{}", stock)
+                .parse()
+                .expect("Failed to parse substitute")],
+            ..Config::default()
+        };
+        CoffeeMachine::new(&config)
+    }
+
+    #[test]
+    fn a_substitute_is_reserved_when_the_ingredient_runs_out_and_released_if_not_prepared() {
+        let milk = {
+            let coffee_machine = CoffeeMachine::new(&Config::default());
+            let cold_milk = *coffee_machine
+                .cold_milk_container
+                .lock()
+                .get_current_amount();
+            let milk_foam = *coffee_machine
+                .milk_foam_container
+                .lock()
+                .get_current_amount();
+            cold_milk + milk_foam + 1
+        };
+        let coffee_machine = with_oat_milk(milk);
+        let details = OrderDetails {
+            substitutes: vec![String::from("avena")],
+            ..OrderDetails::default()
+        };
+        let mut order = Order::new(0, milk, 0, None).with_details(details);
+
+        assert!(coffee_machine.reserve_ingredients(&mut order, &dispenser()));
+        assert_eq!(order.get_substitute(Resource::Milk), Some("avena"));
+        assert_eq!(*coffee_machine.reserved.lock().unwrap(), [0, 0, 0]);
+        assert_eq!(
+            coffee_machine.substitutes.stock(),
+            [(String::from("avena"), 0)]
+        );
+
+        coffee_machine.release_substitutes(&order);
+        assert_eq!(
+            coffee_machine.substitutes.stock(),
+            [(String::from("avena"), milk)]
+        );
+    }
+
+    #[test]
+    fn an_order_whose_customer_left_gives_back_its_substitutes() {
+        let coffee_machine = with_oat_milk(10);
+        let accepted = [String::from("avena")];
+        let reservation = coffee_machine
+            .substitutes
+            .reserve(Resource::Milk, 4, &accepted);
+        assert_eq!(reservation, Reservation::Reserved(String::from("avena")));
+        // Como un pedido reencolado después de que se cayera su dispenser.
+        let mut order = Order::new(0, 4, 0, Some(Duration::from_millis(1)));
+        order.substitute(Resource::Milk, String::from("avena"));
+        thread::sleep(Duration::from_millis(5));
+
+        coffee_machine.dispatcher.push(Message::Job(order));
+        coffee_machine.dispatcher.push(Message::Shutdown);
+        coffee_machine.make_drink(dispenser());
+
+        assert_eq!(
+            coffee_machine.substitutes.stock(),
+            [(String::from("avena"), 10)]
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use super::CoffeeMachine;
+use crate::constants::{COLOR_RED, COLOR_RESET, DISPENSER_JOIN_INTERVAL, MAX_ORDER_CRASHES};
+use crate::dispenser::Dispenser;
+use crate::shutdown::panic_message;
+use crate::stats::DispenserStats;
+use crate::utils::{Message, Resource};
+use crate::Order;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+impl CoffeeMachine {
+    /// Starts the thread of the dispenser
+    /// It is not guarded like the other threads, since the supervisor restarts it if it crashes
+    pub(super) fn spawn_dispenser(self: &Arc<Self>, dispenser: Dispenser) {
+        let coffee_machine_clone = self.clone();
+        let handle = thread::spawn(move || coffee_machine_clone.make_drink(dispenser));
+        self.dispenser_handles
+            .lock()
+            .expect("Failed to lock dispenser_handles")
+            .push((dispenser, handle));
+    }
+
+    /// Waits for every dispenser to finish, including the ones added while waiting
+    /// Meanwhile, it supervises them: the ones that crashed are recovered
+    /// Once the machine is stopping, it only waits until the drain timeout expires
+    /// Returns whether every dispenser finished
+    pub(super) fn supervise_dispensers(self: &Arc<Self>) -> bool {
+        loop {
+            let deadline = self
+                .stop_reason
+                .lock()
+                .expect("Failed to lock stop_reason")
+                .as_ref()
+                .map(|(_, deadline)| *deadline);
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            let mut handles = self
+                .dispenser_handles
+                .lock()
+                .expect("Failed to lock dispenser_handles");
+            if handles.is_empty() {
+                return true;
+            }
+            // Solo espero a los dispensers que ya terminaron, para poder respetar el tiempo
+            // límite si llega una señal mientras espero.
+            let (finished, running) = handles
+                .drain(..)
+                .partition(|(_, handle)| handle.is_finished());
+            *handles = running;
+            drop(handles);
+            for (dispenser, handle) in finished {
+                if let Err(payload) = handle.join() {
+                    self.recover_dispenser(dispenser, panic_message(payload.as_ref()));
+                }
+            }
+            thread::sleep(Duration::from_millis(DISPENSER_JOIN_INTERVAL));
+        }
+    }
+
+    /// Handles the crash of a dispenser
+    /// The order it was preparing is queued again, unless it already made dispensers crash
+    /// `MAX_ORDER_CRASHES` times, in which case it is discarded. The dispenser is restarted up
+    /// to `max_restarts` times, and after that it is left out of service
+    fn recover_dispenser(self: &Arc<Self>, dispenser: Dispenser, error: String) {
+        let n_dispenser = dispenser.get_id();
+        println!(
+            "{}[Supervisor]{} - El dispenser {} se cayó: {}",
+            COLOR_RED, COLOR_RESET, n_dispenser, error
+        );
+        let crashes = self
+            .dispenser_stats
+            .lock()
+            .expect("Failed to lock dispenser_stats")
+            .entry(n_dispenser)
+            .or_insert_with(DispenserStats::new)
+            .record_crash();
+        let mut crash_stats = self.crash_stats.lock().expect("Failed to lock crash_stats");
+        crash_stats.record_crash();
+
+        if let Some(mut order) = self.take_back_in_flight(n_dispenser) {
+            // El pedido conserva su vaso y sus sustitutos, así que no se vuelven a tomar al reintentarlo.
+            if order.record_crash() < MAX_ORDER_CRASHES {
+                println!(
+                    "{}[Supervisor]{} - Reencolando el pedido que estaba en preparación: {}",
+                    COLOR_RED, COLOR_RESET, order
+                );
+                crash_stats.record_requeued();
+                self.dispatcher.push(Message::Job(order));
+            } else {
+                println!(
+                    "{}[Supervisor]{} - El pedido hizo caer {} dispensers, descartándolo: {}",
+                    COLOR_RED, COLOR_RESET, MAX_ORDER_CRASHES, order
+                );
+                self.release_substitutes(&order);
+                self.return_cup(&order);
+                crash_stats.record_discarded();
+            }
+        }
+
+        if crashes <= self.max_restarts {
+            println!(
+                "{}[Supervisor]{} - Reiniciando el dispenser {} (reinicio {} de {})",
+                COLOR_RED, COLOR_RESET, n_dispenser, crashes, self.max_restarts
+            );
+            crash_stats.record_restart();
+            drop(crash_stats);
+            self.spawn_dispenser(dispenser);
+            return;
+        }
+        drop(crash_stats);
+        println!(
+            "{}[Supervisor]{} - El dispenser {} se cayó demasiadas veces, queda fuera de servicio",
+            COLOR_RED, COLOR_RESET, n_dispenser
+        );
+        self.dispenser_stats
+            .lock()
+            .expect("Failed to lock dispenser_stats")
+            .entry(n_dispenser)
+            .or_insert_with(DispenserStats::new)
+            .mark_out_of_service();
+        self.dispatcher.unregister(&dispenser);
+        let remaining_dispensers = {
+            let mut active_dispensers = self
+                .active_dispensers
+                .lock()
+                .expect("Failed to lock active_dispensers");
+            // Si ya se le había pedido que se retire, ya no se contaba como activo.
+            if self
+                .leaving
+                .lock()
+                .expect("Failed to lock leaving")
+                .insert(n_dispenser)
+            {
+                *active_dispensers -= 1;
+            }
+            *active_dispensers
+        };
+        if remaining_dispensers == 0 {
+            self.fail(
+                &format!("el dispenser {}", n_dispenser),
+                String::from("se cayó demasiadas veces y no quedan dispensers en servicio"),
+            );
+        }
+    }
+
+    /// Takes back the order the dispenser was preparing, if any, giving back the ingredients
+    /// still set aside for it. The ones already served were given back when served
+    fn take_back_in_flight(&self, n_dispenser: u64) -> Option<Order> {
+        let in_flight = self
+            .in_flight
+            .lock()
+            .expect("Failed to lock in_flight")
+            .remove(&n_dispenser)?;
+        for ingredient in Resource::ALL {
+            self.release_ingredient(ingredient, in_flight.reserved[ingredient as usize]);
+        }
+        Some(in_flight.order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coffee_machine::tests::dispenser;
+    use crate::coffee_machine::InFlightOrder;
+    use crate::config::Config;
+    use crate::order::Size;
+
+    /// Makes the thread of the dispenser panic while it prepares the order.
+    fn crash(coffee_machine: &Arc<CoffeeMachine>, dispenser: Dispenser, order: Order) {
+        coffee_machine.in_flight.lock().unwrap().insert(
+            dispenser.get_id(),
+            InFlightOrder {
+                order,
+                reserved: [0; 3],
+            },
+        );
+        let handle = thread::spawn(|| panic!("falla inyectada"));
+        coffee_machine
+            .dispenser_handles
+            .lock()
+            .unwrap()
+            .push((dispenser, handle));
+    }
+
+    #[test]
+    fn a_crashed_order_gives_back_only_what_was_not_served_and_keeps_its_cup() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        let dispenser = dispenser();
+        let cups = coffee_machine.container(Size::default().cups());
+        let initial_cups = *cups.lock().get_current_amount();
+        let mut order = Order::new(2, 3, 0, None);
+
+        assert!(coffee_machine.reserve_ingredients(&mut order, &dispenser));
+        assert!(coffee_machine.take_cup(&order, &dispenser));
+        order.mark_cup_taken();
+        coffee_machine.start_preparing(dispenser.get_id(), &order);
+        coffee_machine.apply_ingredient(Resource::Coffee, &order, &dispenser);
+        assert_eq!(*coffee_machine.reserved.lock().unwrap(), [0, 3, 0]);
+
+        let order = coffee_machine
+            .take_back_in_flight(dispenser.get_id())
+            .expect("Failed to find the order in preparation");
+        assert_eq!(*coffee_machine.reserved.lock().unwrap(), [0, 0, 0]);
+        assert!(order.has_cup());
+        assert_eq!(*cups.lock().get_current_amount(), initial_cups - 1);
+
+        coffee_machine.return_cup(&order);
+        assert_eq!(*cups.lock().get_current_amount(), initial_cups);
+    }
+
+    #[test]
+    fn a_crashed_dispenser_is_restarted_until_it_crashes_too_many_times() {
+        let config = Config {
+            max_restarts: 1,
+            ..Config::default()
+        };
+        let coffee_machine = CoffeeMachine::new(&config);
+        // Solo puede hacer pedidos de agua, así al reiniciarlo no toma el pedido reencolado.
+        let dispenser = Dispenser::new(1, "water-only".parse().unwrap());
+        coffee_machine.dispensers.lock().unwrap().push(dispenser);
+        coffee_machine.dispatcher.register(&dispenser);
+        *coffee_machine.active_dispensers.lock().unwrap() = 1;
+        coffee_machine.dispatcher.push(Message::Shutdown);
+
+        crash(&coffee_machine, dispenser, Order::new(1, 1, 1, None));
+        assert!(coffee_machine.supervise_dispensers());
+        let mut requeued = coffee_machine.dispatcher.waiting_orders();
+        let order = requeued.pop().expect("Failed to requeue the order");
+        assert!(requeued.is_empty());
+        assert!(!coffee_machine.is_stopping());
+
+        coffee_machine.dispatcher.drain();
+        crash(&coffee_machine, dispenser, order);
+        assert!(coffee_machine.supervise_dispensers());
+        assert!(coffee_machine.dispatcher.waiting_orders().is_empty());
+        assert_eq!(
+            coffee_machine.crash_stats.lock().unwrap().to_string(),
+            "Caídas de dispensers: 2 - Reinicios: 1 - Pedidos reencolados: 1 - Pedidos descartados: 1"
+        );
+        assert!(coffee_machine.dispenser_stats.lock().unwrap()[&1]
+            .to_string()
+            .ends_with("Caídas: 2 (fuera de servicio)"));
+        // Era el único dispenser, así que la máquina se detiene.
+        assert!(coffee_machine.is_stopping());
+    }
+}
@@ -0,0 +1,313 @@
+use super::CoffeeMachine;
+use crate::alert::AlertEvent;
+use crate::constants::{
+    COLOR_BLUE, COLOR_RESET, DELIVERY_CHECK_INTERVAL, FORECAST_UPDATE_INTERVAL,
+};
+use crate::container::{Container, ContainerKind, ContainerMonitor, Signal};
+use crate::delivery::Delivery;
+use crate::forecast::{format_eta, queued_demand};
+use crate::stats::{AlertStats, DeliveryRecord, PurchaseRecord, PurchaseStatus};
+use crate::supplier::PurchaseOrder;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, MutexGuard};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+impl CoffeeMachine {
+    /// Waits until a raw material container that ran out receives a delivery, or the machine
+    /// shuts down
+    pub(super) fn wait_for_delivery(
+        &self,
+        container: &ContainerMonitor,
+        guard: MutexGuard<Container>,
+    ) {
+        let _guard = container.wait_while(Signal::RefillCompleted, guard, |container| {
+            !container.has_any() && !self.should_shutdown.load(Ordering::Relaxed)
+        });
+    }
+
+    /// Spawns a thread that will receive the scheduled deliveries
+    pub(super) fn delivery_scheduler(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(String::from("la recepción de entregas"), |coffee_machine| {
+            coffee_machine.receive_deliveries()
+        })
+    }
+
+    /// Receives each scheduled delivery once its time arrives, until the machine shuts down
+    /// Deliveries can be scheduled while waiting, when a purchase order is placed
+    fn receive_deliveries(&self) {
+        while !self.should_shutdown.load(Ordering::Relaxed) {
+            let elapsed = self.started_at.elapsed();
+            let mut due: Vec<Delivery> = {
+                let mut pending_deliveries = self
+                    .pending_deliveries
+                    .lock()
+                    .expect("Failed to lock pending_deliveries");
+                let (due, pending): (Vec<Delivery>, Vec<Delivery>) = pending_deliveries
+                    .iter()
+                    .partition(|delivery| delivery.get_at() <= elapsed);
+                *pending_deliveries = pending;
+                due
+            };
+            due.sort_by_key(|delivery| delivery.get_at());
+            for delivery in due {
+                self.receive_delivery(delivery);
+            }
+            thread::sleep(Duration::from_millis(DELIVERY_CHECK_INTERVAL));
+        }
+    }
+
+    /// Adds the delivered stock to its container, up to the capacity of the container
+    /// Returns the amount that fit in the container
+    fn receive_delivery(&self, delivery: Delivery) -> u64 {
+        let kind = delivery.get_container();
+        let container = self.container(kind);
+        let mut stock = container.lock();
+        let accepted = delivery
+            .get_amount()
+            .min(kind.capacity().saturating_sub(*stock.get_current_amount()));
+        stock.add(&accepted);
+        container.notify_refill(&stock);
+        println!(
+            "{}[Entregas]{} - Llegó una entrega de {} de {}: se agregaron {} (nivel actual: {})",
+            COLOR_BLUE,
+            COLOR_RESET,
+            delivery.get_amount(),
+            kind,
+            accepted,
+            stock.get_current_amount()
+        );
+        drop(stock);
+        self.delivery_records
+            .lock()
+            .expect("Failed to lock delivery_records")
+            .push(DeliveryRecord::new(
+                delivery,
+                self.started_at.elapsed(),
+                accepted,
+            ));
+        if let Some(id) = delivery.get_purchase_order() {
+            let mut purchase_records = self
+                .purchase_records
+                .lock()
+                .expect("Failed to lock purchase_records");
+            if let Some(record) = purchase_records
+                .iter_mut()
+                .find(|record| record.get_order().get_id() == id)
+            {
+                record.record_received(accepted);
+            }
+        }
+        accepted
+    }
+
+    /// Places a purchase order for the raw material container of the given kind if its stock,
+    /// once the waiting orders are served and the supplier lead time passes at the recent
+    /// consumption rate, is expected to be below its reorder point
+    /// The order asks for enough to fill the container, and only one order per container can
+    /// be waiting for its delivery
+    fn check_reorder(&self, kind: ContainerKind, stock: u64, queued_demand: u64, rate: f64) {
+        let reorder_point = match self.reorder_points[kind as usize] {
+            Some(reorder_point) => reorder_point,
+            None => return,
+        };
+        let mut purchase_records = self
+            .purchase_records
+            .lock()
+            .expect("Failed to lock purchase_records");
+        if purchase_records
+            .iter()
+            .any(|record| record.is_pending() && record.get_order().get_container() == kind)
+        {
+            return;
+        }
+        let lead_time_demand = (rate * self.supplier.lead_time().as_secs_f64()) as u64;
+        let projected = stock
+            .saturating_sub(queued_demand)
+            .saturating_sub(lead_time_demand);
+        if projected >= reorder_point {
+            return;
+        }
+
+        let order = PurchaseOrder::new(
+            purchase_records.len() as u64 + 1,
+            kind,
+            kind.capacity() - projected,
+            self.started_at.elapsed(),
+        );
+        let status = match self.supplier.place_order(&order) {
+            Ok(delivery) => {
+                println!(
+                    "{}[Compras]{} - {}: el proveedor entregará {} a los {:.1}s (stock proyectado: {})",
+                    COLOR_BLUE,
+                    COLOR_RESET,
+                    order,
+                    delivery.get_amount(),
+                    delivery.get_at().as_secs_f64(),
+                    projected
+                );
+                self.pending_deliveries
+                    .lock()
+                    .expect("Failed to lock pending_deliveries")
+                    .push(delivery);
+                PurchaseStatus::Pending(delivery.get_amount())
+            }
+            Err(reason) => {
+                println!(
+                    "{}[Compras]{} - {}: rechazada, {}",
+                    COLOR_BLUE, COLOR_RESET, order, reason
+                );
+                PurchaseStatus::Rejected(reason)
+            }
+        };
+        purchase_records.push(PurchaseRecord::new(order, status));
+    }
+
+    /// Spawns a thread that will forecast when each container runs out
+    pub(super) fn forecast_system(self: &Arc<Self>) -> JoinHandle<()> {
+        self.spawn_actor(
+            String::from("el pronóstico de agotamiento"),
+            |coffee_machine| coffee_machine.forecast_depletion(),
+        )
+    }
+
+    /// Forecasts periodically when each container runs out, from its recent consumption and
+    /// the demand of the waiting orders
+    /// If a forecast horizon is configured, it alerts once when a container is expected to run
+    /// out within it, and again when it is not anymore
+    fn forecast_depletion(&self) {
+        let mut alerted = [false; ContainerKind::COUNT];
+        while !self.should_shutdown.load(Ordering::Relaxed) {
+            let demand = queued_demand(
+                &self.dispatcher.waiting_orders(),
+                *self
+                    .ground_coffee_beans_container
+                    .lock()
+                    .get_current_amount(),
+                *self.milk_foam_container.lock().get_current_amount(),
+            );
+            let now = Instant::now();
+            for kind in ContainerKind::ALL {
+                let (stock, used) = {
+                    let container = self.container(kind).lock();
+                    (
+                        *container.get_current_amount(),
+                        *container.get_amount_used(),
+                    )
+                };
+                let (eta, rate) = {
+                    let mut forecasts = self.forecasts.lock().expect("Failed to lock forecasts");
+                    let forecast = &mut forecasts[kind as usize];
+                    let eta = forecast.update(now, stock, used, demand[kind as usize]);
+                    (eta, forecast.get_rate())
+                };
+                self.check_reorder(kind, stock, demand[kind as usize], rate);
+                if let Some(horizon) = self.forecast_horizon {
+                    let expected = eta.is_some_and(|eta| eta < horizon);
+                    if expected && !alerted[kind as usize] {
+                        self.emit_alert(
+                            kind,
+                            AlertEvent::Forecast,
+                            None,
+                            stock,
+                            format!(
+                                "Se estima que se agota en {} (horizonte de {}s)",
+                                format_eta(eta),
+                                horizon.as_secs()
+                            ),
+                        );
+                        self.update_alert_stats(kind, AlertStats::record_forecast);
+                    } else if !expected && alerted[kind as usize] {
+                        self.emit_alert(
+                            kind,
+                            AlertEvent::ForecastCleared,
+                            None,
+                            stock,
+                            format!(
+                                "Ya no se estima que se agote dentro del horizonte (agotamiento estimado: {})",
+                                format_eta(eta)
+                            ),
+                        );
+                    }
+                    alerted[kind as usize] = expected;
+                }
+            }
+            thread::sleep(Duration::from_millis(FORECAST_UPDATE_INTERVAL));
+        }
+    }
+
+    /// Returns the last estimated time until the container of the given kind runs out
+    pub(super) fn depletion_eta(&self, kind: ContainerKind) -> String {
+        format_eta(
+            self.forecasts.lock().expect("Failed to lock forecasts")[kind as usize].get_eta(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::Threshold;
+    use crate::config::Config;
+
+    #[test]
+    fn a_delivery_only_adds_what_fits_in_the_container() {
+        let coffee_machine = CoffeeMachine::new(&Config::default());
+        let capacity = ContainerKind::CoffeeBeans.capacity();
+        coffee_machine
+            .container(ContainerKind::CoffeeBeans)
+            .lock()
+            .subtract(&500);
+
+        let accepted = coffee_machine.receive_delivery(Delivery::new(
+            Duration::ZERO,
+            ContainerKind::CoffeeBeans,
+            800,
+        ));
+
+        assert_eq!(accepted, 500);
+        assert_eq!(
+            *coffee_machine
+                .container(ContainerKind::CoffeeBeans)
+                .lock()
+                .get_current_amount(),
+            capacity
+        );
+    }
+
+    #[test]
+    fn a_partially_filled_purchase_order_records_what_was_received() {
+        let mut config = Config::default();
+        let capacity = ContainerKind::ColdMilk.capacity();
+        config.reorder_points[ContainerKind::ColdMilk as usize] =
+            Some(Threshold::Absolute(capacity));
+        config.supplier.failure_rate = 0.0;
+        config.supplier.partial_rate = 1.0;
+        config.supplier.seed = Some(7);
+        let coffee_machine = CoffeeMachine::new(&config);
+        coffee_machine
+            .container(ContainerKind::ColdMilk)
+            .lock()
+            .subtract(&1000);
+
+        coffee_machine.check_reorder(ContainerKind::ColdMilk, capacity - 1000, 0, 0.0);
+        coffee_machine.check_reorder(ContainerKind::ColdMilk, capacity - 1000, 0, 0.0);
+        let pending: Vec<Delivery> = coffee_machine
+            .pending_deliveries
+            .lock()
+            .unwrap()
+            .drain(..)
+            .collect();
+        assert_eq!(pending.len(), 1);
+        assert!(pending[0].get_amount() < 1000);
+        coffee_machine.receive_delivery(pending[0]);
+
+        let records = coffee_machine.purchase_records.lock().unwrap();
+        assert!(!records[0].is_pending());
+        assert!(records[0].to_string().ends_with(&format!(
+            "(parcial), se agregaron {}",
+            pending[0].get_amount()
+        )));
+    }
+}
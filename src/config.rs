@@ -0,0 +1,513 @@
+use crate::alert::{AlertSinkSpec, AlertThresholds, Severity, Threshold};
+use crate::arrival::ArrivalProcess;
+use crate::constants::{
+    DEFAULT_ALERT_RATE_LIMIT, DEFAULT_ALERT_REMINDER_INTERVAL, DEFAULT_COMMENT, DEFAULT_DELIMITER,
+    DEFAULT_DRAIN_TIMEOUT, DEFAULT_FORECAST_HORIZON, DEFAULT_GENERATED_ORDERS,
+    DEFAULT_INVALID_ORDER_POLICY, DEFAULT_MAX_DISPENSER_RESTARTS, DEFAULT_ORDER_PATIENCE,
+    DEFAULT_REJECTS_FILE, DEFAULT_SCHEDULING_POLICY, DEFAULT_SUPPLIER_LEAD_TIME,
+    DEFAULT_UNSTARTED_ORDERS_FILE, MAX_DISPENSERS, MAX_GENERATED_AMOUNT,
+    ORDER_TIME_INTERVAL_ARRIVAL,
+};
+use crate::container::ContainerKind;
+use crate::delivery::{read_deliveries, Delivery};
+use crate::dispatcher::DispatcherKind;
+use crate::dispenser::DispenserSpec;
+use crate::generator::{AmountDistribution, Mix, CUSTOM_DRINK};
+use crate::menu::Menu;
+use crate::order::Size;
+use crate::order_input::{SourceLocation, SourceSpec};
+use crate::order_reader::InvalidOrderPolicy;
+use crate::order_source::InputFormat;
+use crate::scheduling_policy::PolicyKind;
+use crate::substitution::SubstituteSpec;
+use crate::supplier::MockSupplierSpec;
+use crate::utils::Resource;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone)]
+/// Runtime configuration of the coffee machine, taken from the command line arguments.
+pub struct Config {
+    /// Policy used by the dispensers to choose the next order
+    pub policy: PolicyKind,
+    /// If set, the same input is run once per policy and their latencies are compared
+    pub compare_policies: bool,
+    /// Patience of the customers whose orders do not specify one
+    pub default_patience: Option<Duration>,
+    /// Hardware of each dispenser of the machine
+    pub dispensers: Vec<DispenserSpec>,
+    /// Minimum and maximum amount of dispensers, if the autoscaler is enabled
+    pub autoscale: Option<(usize, usize)>,
+    /// How orders are handed to the dispensers
+    pub dispatcher: DispatcherKind,
+    /// If set, the dispatchers are benchmarked with this amount of orders instead of running the machine
+    pub benchmark_orders: Option<u64>,
+    /// If set, the ground coffee and milk foam containers use a single condition variable for
+    /// every signal, waking up every waiting thread on each change
+    pub shared_signalling: bool,
+    /// Time between reminders of a low level alert, if reminders are enabled
+    pub alert_reminder: Option<Duration>,
+    /// Thresholds of each alert severity, indexed by container
+    pub alert_thresholds: [AlertThresholds; ContainerKind::COUNT],
+    /// Where alerts are delivered, besides the console
+    pub alert_sinks: Vec<AlertSinkSpec>,
+    /// Maximum alerts delivered to each sink per `ALERT_RATE_LIMIT_WINDOW`, if limited
+    pub alert_rate_limit: Option<usize>,
+    /// Estimated time until a container runs out below which an alert is raised, if enabled
+    pub forecast_horizon: Option<Duration>,
+    /// Stock of raw materials that arrives while the machine is running
+    pub deliveries: Vec<Delivery>,
+    /// Projected stock below which a purchase order is placed, indexed by container.
+    /// Only raw material containers can have one
+    pub reorder_points: [Option<Threshold>; ContainerKind::COUNT],
+    /// Behavior of the supplier that receives the purchase orders
+    pub supplier: MockSupplierSpec,
+    /// Time the dispensers have to finish the drinks in preparation after a termination signal
+    pub drain_timeout: Duration,
+    /// Times a crashed dispenser is restarted before leaving it out of service
+    pub max_restarts: u64,
+    /// File where the orders nobody started to prepare are saved after a termination signal
+    pub unstarted_orders_path: PathBuf,
+    /// Sources the orders are read from, each one by its own reader. If empty, they are read
+    /// from the standard input
+    pub sources: Vec<SourceSpec>,
+    /// Format of the orders input. If `None`, it is guessed from the extension of each file
+    pub input_format: Option<InputFormat>,
+    /// When the orders without an arrival time reach the machine
+    pub arrival_process: ArrivalProcess,
+    /// Replay speed of the arrival times of the orders
+    pub arrival_speed: f64,
+    /// Seed of the Poisson arrivals, to make them reproducible
+    pub arrival_seed: Option<u64>,
+    /// Drinks that can be ordered by name
+    pub menu: Arc<Menu>,
+    /// Ingredients that replace the ones that run out, in the order they are tried
+    pub substitutes: Vec<SubstituteSpec>,
+    /// What the reader does with the rows that are not valid orders
+    pub on_invalid_order: InvalidOrderPolicy,
+    /// File where the quarantined rows are written
+    pub rejects_path: PathBuf,
+    /// Character that separates the columns of the orders file
+    pub delimiter: u8,
+    /// Character that starts the comment lines of the orders file, if there are comments
+    pub comment: Option<u8>,
+    /// File where the final report is written, besides the console
+    pub report_path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            policy: DEFAULT_SCHEDULING_POLICY
+                .parse()
+                .expect("Invalid default scheduling policy"),
+            compare_policies: false,
+            default_patience: DEFAULT_ORDER_PATIENCE.map(Duration::from_millis),
+            dispensers: vec![DispenserSpec::default(); MAX_DISPENSERS as usize],
+            autoscale: None,
+            dispatcher: DispatcherKind::SharedQueue,
+            benchmark_orders: None,
+            shared_signalling: false,
+            alert_reminder: DEFAULT_ALERT_REMINDER_INTERVAL.map(Duration::from_millis),
+            alert_thresholds: [AlertThresholds::default(); ContainerKind::COUNT],
+            alert_sinks: Vec::new(),
+            alert_rate_limit: DEFAULT_ALERT_RATE_LIMIT,
+            forecast_horizon: DEFAULT_FORECAST_HORIZON.map(Duration::from_secs),
+            deliveries: Vec::new(),
+            reorder_points: [None; ContainerKind::COUNT],
+            supplier: MockSupplierSpec {
+                lead_time: Duration::from_millis(DEFAULT_SUPPLIER_LEAD_TIME),
+                failure_rate: 0.0,
+                partial_rate: 0.0,
+                seed: None,
+            },
+            drain_timeout: Duration::from_millis(DEFAULT_DRAIN_TIMEOUT),
+            max_restarts: DEFAULT_MAX_DISPENSER_RESTARTS,
+            unstarted_orders_path: PathBuf::from(DEFAULT_UNSTARTED_ORDERS_FILE),
+            sources: Vec::new(),
+            input_format: None,
+            arrival_process: ArrivalProcess::Fixed(Duration::from_millis(
+                ORDER_TIME_INTERVAL_ARRIVAL,
+            )),
+            arrival_speed: 1.0,
+            arrival_seed: None,
+            menu: Arc::new(Menu::default()),
+            substitutes: Vec::new(),
+            on_invalid_order: DEFAULT_INVALID_ORDER_POLICY
+                .parse()
+                .expect("Invalid default invalid order policy"),
+            rejects_path: PathBuf::from(DEFAULT_REJECTS_FILE),
+            delimiter: DEFAULT_DELIMITER,
+            comment: DEFAULT_COMMENT,
+            report_path: None,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the command line arguments (without the program name).
+    ///
+    /// Supported arguments:
+    /// - `--policy <fifo|sjf|edf|resource-aware>`
+    /// - `--compare-policies`
+    /// - `--patience <milliseconds>`
+    /// - `--dispenser <capabilities>[:<speed>]`, once per dispenser. If not given, there are
+    ///   `MAX_DISPENSERS` dispensers able to make any drink
+    /// - `--autoscale <min>:<max>`
+    /// - `--dispatcher <shared|work-stealing>`
+    /// - `--benchmark-dispatchers <orders>`
+    /// - `--shared-condvar`
+    /// - `--alert-reminder <milliseconds>`
+    /// - `--alert-threshold <container>:<warning|critical|empty>=<threshold>`, where the
+    ///   threshold is an amount (`500`) or a fraction of the capacity (`0.25`)
+    /// - `--alert-log <path>`, `--alert-webhook <http-url>` and `--alert-command <command>`,
+    ///   each of them repeatable
+    /// - `--alert-rate-limit <alerts-per-window>`
+    /// - `--forecast-horizon <seconds>`
+    /// - `--deliveries <path>`, a file with a `<milliseconds>,<container>,<amount>` row per delivery
+    /// - `--reorder-point <container>:<threshold>`, repeatable
+    /// - `--supplier-lead-time <milliseconds>`
+    /// - `--supplier-failure-rate <probability>` and `--supplier-partial-rate <probability>`
+    /// - `--supplier-seed <seed>`
+    /// - `--drain-timeout <milliseconds>`
+    /// - `--max-restarts <restarts>`
+    /// - `--unstarted-orders <path>`
+    /// - `--orders <path>`
+    /// - `--source <name>=<path|-|tcp:address>`, repeatable
+    /// - `--input-format <csv|jsonl>`. If not given, it is guessed from the extension of each
+    ///   orders file
+    /// - `--arrival-interval <milliseconds>`
+    /// - `--arrival-rate <rate>` or `--arrival-rate <seconds>:<rate>,...`, in orders per second
+    /// - `--arrival-speed <factor>`
+    /// - `--arrival-seed <seed>`
+    /// - `--menu <path>`, a JSON file with the drinks that can be ordered by name
+    /// - `--substitute <ingredient>=<substitute>:<stock>`, repeatable
+    /// - `--on-invalid-order <skip|stop|quarantine>`
+    /// - `--rejects <path>`
+    /// - `--delimiter <character|tab>`
+    /// - `--comment <character|none>`
+    /// - `--report <path>`
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut dispensers = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--policy" => config.policy = next_value(&mut args, &arg)?.parse()?,
+                "--compare-policies" => config.compare_policies = true,
+                "--patience" => {
+                    config.default_patience =
+                        Some(Duration::from_millis(parse_number(&mut args, &arg)?))
+                }
+                "--dispenser" => dispensers.push(next_value(&mut args, &arg)?.parse()?),
+                "--autoscale" => config.autoscale = Some(parse_range(&mut args, &arg)?),
+                "--dispatcher" => config.dispatcher = next_value(&mut args, &arg)?.parse()?,
+                "--shared-condvar" => config.shared_signalling = true,
+                "--alert-reminder" => {
+                    config.alert_reminder =
+                        Some(Duration::from_millis(parse_number(&mut args, &arg)?))
+                }
+                "--alert-threshold" => {
+                    let (container, severity, threshold) = parse_alert_threshold(&mut args, &arg)?;
+                    config.alert_thresholds[container as usize].set(severity, threshold);
+                }
+                "--alert-log" => config
+                    .alert_sinks
+                    .push(AlertSinkSpec::File(next_value(&mut args, &arg)?.into())),
+                "--alert-webhook" => config.alert_sinks.push(AlertSinkSpec::Webhook(
+                    next_value(&mut args, &arg)?.parse()?,
+                )),
+                "--alert-command" => config
+                    .alert_sinks
+                    .push(AlertSinkSpec::Command(next_value(&mut args, &arg)?)),
+                "--alert-rate-limit" => {
+                    config.alert_rate_limit = Some(parse_number(&mut args, &arg)? as usize)
+                }
+                "--forecast-horizon" => {
+                    config.forecast_horizon =
+                        Some(Duration::from_secs(parse_number(&mut args, &arg)?))
+                }
+                "--deliveries" => {
+                    config.deliveries = read_deliveries(next_value(&mut args, &arg)?.as_ref())?
+                }
+                "--reorder-point" => {
+                    let (container, threshold) = parse_reorder_point(&mut args, &arg)?;
+                    config.reorder_points[container as usize] = Some(threshold);
+                }
+                "--supplier-lead-time" => {
+                    config.supplier.lead_time =
+                        Duration::from_millis(parse_number(&mut args, &arg)?)
+                }
+                "--supplier-failure-rate" => {
+                    config.supplier.failure_rate = parse_probability(&mut args, &arg)?
+                }
+                "--supplier-partial-rate" => {
+                    config.supplier.partial_rate = parse_probability(&mut args, &arg)?
+                }
+                "--supplier-seed" => config.supplier.seed = Some(parse_number(&mut args, &arg)?),
+                "--drain-timeout" => {
+                    config.drain_timeout = Duration::from_millis(parse_number(&mut args, &arg)?)
+                }
+                "--max-restarts" => config.max_restarts = parse_number(&mut args, &arg)?,
+                "--orders" => config.sources.push(SourceSpec {
+                    name: None,
+                    location: SourceLocation::File(next_value(&mut args, &arg)?.into()),
+                }),
+                "--source" => config.sources.push(next_value(&mut args, &arg)?.parse()?),
+                "--input-format" => {
+                    config.input_format = Some(next_value(&mut args, &arg)?.parse()?)
+                }
+                "--menu" => {
+                    config.menu = Arc::new(Menu::load(next_value(&mut args, &arg)?.as_ref())?)
+                }
+                "--arrival-interval" => {
+                    config.arrival_process =
+                        ArrivalProcess::Fixed(Duration::from_millis(parse_number(&mut args, &arg)?))
+                }
+                "--arrival-rate" => {
+                    config.arrival_process =
+                        ArrivalProcess::Poisson(next_value(&mut args, &arg)?.parse()?)
+                }
+                "--arrival-speed" => config.arrival_speed = parse_speed(&mut args, &arg)?,
+                "--arrival-seed" => config.arrival_seed = Some(parse_number(&mut args, &arg)?),
+                "--substitute" => config
+                    .substitutes
+                    .push(next_value(&mut args, &arg)?.parse()?),
+                "--on-invalid-order" => {
+                    config.on_invalid_order = next_value(&mut args, &arg)?.parse()?
+                }
+                "--rejects" => config.rejects_path = next_value(&mut args, &arg)?.into(),
+                "--delimiter" => {
+                    config.delimiter = match next_value(&mut args, &arg)?.as_str() {
+                        "tab" | "\\t" => b'\t',
+                        value => parse_character(value, &arg)?,
+                    }
+                }
+                "--comment" => {
+                    config.comment = match next_value(&mut args, &arg)?.as_str() {
+                        "none" => None,
+                        value => Some(parse_character(value, &arg)?),
+                    }
+                }
+                "--unstarted-orders" => {
+                    config.unstarted_orders_path = next_value(&mut args, &arg)?.into()
+                }
+                "--report" => config.report_path = Some(next_value(&mut args, &arg)?.into()),
+                "--benchmark-dispatchers" => {
+                    config.benchmark_orders = Some(parse_number(&mut args, &arg)?)
+                }
+                _ => return Err(format!("Argumento desconocido: {}", arg)),
+            }
+        }
+        if !dispensers.is_empty() {
+            config.dispensers = dispensers;
+        }
+        Ok(config)
+    }
+}
+
+/// Configuration of the `generate` subcommand, which writes order files instead of running
+/// the coffee machine.
+pub struct GeneratorConfig {
+    /// Orders to write
+    pub count: u64,
+    /// File to write the orders to. If `None`, they are written to the standard output
+    pub output_path: Option<PathBuf>,
+    pub format: InputFormat,
+    /// Seed of the random orders. If `None`, each run writes different orders
+    pub seed: Option<u64>,
+    /// Drinks ordered and how often, where `CUSTOM_DRINK` is a drink with random amounts
+    pub drinks: Mix<String>,
+    /// Sizes ordered and how often. If `None`, the orders have no size
+    pub sizes: Option<Mix<Size>>,
+    /// Distribution of the amount of each ingredient of the custom drinks
+    pub amounts: [AmountDistribution; 3],
+    /// Distribution of the patience (in milliseconds) of the customers. If `None`, the orders
+    /// have no patience
+    pub patience: Option<AmountDistribution>,
+    /// Process the arrival times of the orders follow. If `None`, the orders have no arrival time
+    pub arrival_process: Option<ArrivalProcess>,
+}
+
+impl GeneratorConfig {
+    /// Builds the configuration from the arguments that follow `generate`.
+    ///
+    /// Supported arguments:
+    /// - `--count <orders>`
+    /// - `--output <path>`
+    /// - `--format <csv|jsonl>`. If not given, it is guessed from the extension of the output
+    /// - `--seed <seed>`
+    /// - `--menu <path>`, the menu the drinks are taken from
+    /// - `--drinks <drink>:<weight>,...`, where `custom` is a drink with random amounts. If not
+    ///   given, every drink of the menu is equally likely, or every drink is custom without a menu
+    /// - `--sizes <size>:<weight>,...`
+    /// - `--coffee <distribution>`, `--milk <distribution>` and `--water <distribution>`, where
+    ///   the distribution is `<amount>`, `<min>-<max>` or `poisson:<mean>`
+    /// - `--patience <distribution>`
+    /// - `--arrival-interval <milliseconds>`
+    /// - `--arrival-rate <rate>` or `--arrival-rate <seconds>:<rate>,...`, in orders per second
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = GeneratorConfig {
+            count: DEFAULT_GENERATED_ORDERS,
+            output_path: None,
+            format: InputFormat::Csv,
+            seed: None,
+            drinks: Mix::uniform([String::from(CUSTOM_DRINK)]),
+            sizes: None,
+            amounts: [AmountDistribution::Uniform(0, MAX_GENERATED_AMOUNT); 3],
+            patience: None,
+            arrival_process: None,
+        };
+        let mut menu = Menu::default();
+        let mut drinks: Option<Mix<String>> = None;
+        let mut format = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--count" => config.count = parse_number(&mut args, &arg)?,
+                "--output" => config.output_path = Some(next_value(&mut args, &arg)?.into()),
+                "--format" => format = Some(next_value(&mut args, &arg)?.parse()?),
+                "--seed" => config.seed = Some(parse_number(&mut args, &arg)?),
+                "--menu" => menu = Menu::load(next_value(&mut args, &arg)?.as_ref())?,
+                "--drinks" => drinks = Some(next_value(&mut args, &arg)?.to_lowercase().parse()?),
+                "--sizes" => config.sizes = Some(next_value(&mut args, &arg)?.parse()?),
+                "--coffee" => {
+                    config.amounts[Resource::Coffee as usize] =
+                        next_value(&mut args, &arg)?.parse()?
+                }
+                "--milk" => {
+                    config.amounts[Resource::Milk as usize] =
+                        next_value(&mut args, &arg)?.parse()?
+                }
+                "--water" => {
+                    config.amounts[Resource::Water as usize] =
+                        next_value(&mut args, &arg)?.parse()?
+                }
+                "--patience" => config.patience = Some(next_value(&mut args, &arg)?.parse()?),
+                "--arrival-interval" => {
+                    config.arrival_process = Some(ArrivalProcess::Fixed(Duration::from_millis(
+                        parse_number(&mut args, &arg)?,
+                    )))
+                }
+                "--arrival-rate" => {
+                    config.arrival_process = Some(ArrivalProcess::Poisson(
+                        next_value(&mut args, &arg)?.parse()?,
+                    ))
+                }
+                _ => return Err(format!("Argumento desconocido: {}", arg)),
+            }
+        }
+        match drinks {
+            Some(drinks) => {
+                if let Some(drink) = drinks
+                    .options()
+                    .find(|drink| *drink != CUSTOM_DRINK && !menu.has_drink(drink))
+                {
+                    return Err(format!("La bebida {} no está en el menú", drink));
+                }
+                config.drinks = drinks;
+            }
+            None if menu.drinks().next().is_some() => {
+                config.drinks = Mix::uniform(menu.drinks().cloned())
+            }
+            None => {}
+        }
+        config.format = format
+            .or_else(|| config.output_path.as_deref().map(InputFormat::from_path))
+            .unwrap_or(config.format);
+        Ok(config)
+    }
+}
+
+/// Returns the value that follows the given flag, parsed as a number.
+fn parse_number(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<u64, String> {
+    let value = next_value(args, flag)?;
+    value
+        .parse()
+        .map_err(|_| format!("Valor inválido para {}: {}", flag, value))
+}
+
+/// Returns the `<min>:<max>` range that follows the given flag.
+fn parse_range(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<(usize, usize), String> {
+    let value = next_value(args, flag)?;
+    let invalid = || format!("Valor inválido para {}: {}", flag, value);
+    let (min, max) = value.split_once(':').ok_or_else(invalid)?;
+    let min: usize = min.parse().map_err(|_| invalid())?;
+    let max: usize = max.parse().map_err(|_| invalid())?;
+    if min == 0 || min > max {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
+
+/// Returns the `<container>:<severity>=<threshold>` that follows the given flag.
+fn parse_alert_threshold(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<(ContainerKind, Severity, Threshold), String> {
+    let value = next_value(args, flag)?;
+    let invalid = || format!("Valor inválido para {}: {}", flag, value);
+    let (container, rest) = value.split_once(':').ok_or_else(invalid)?;
+    let (severity, threshold) = rest.split_once('=').ok_or_else(invalid)?;
+    Ok((container.parse()?, severity.parse()?, threshold.parse()?))
+}
+
+/// Returns the `<container>:<threshold>` that follows the given flag, for a raw material container.
+fn parse_reorder_point(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<(ContainerKind, Threshold), String> {
+    let value = next_value(args, flag)?;
+    let (container, threshold) = value
+        .split_once(':')
+        .ok_or_else(|| format!("Valor inválido para {}: {}", flag, value))?;
+    let container: ContainerKind = container.parse()?;
+    if !container.is_raw() {
+        return Err(format!(
+            "Solo se pueden hacer pedidos de materia prima o vasos, no de {}",
+            container.id()
+        ));
+    }
+    Ok((container, threshold.parse()?))
+}
+
+/// Returns the positive factor that follows the given flag.
+fn parse_speed(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<f64, String> {
+    let value = next_value(args, flag)?;
+    value
+        .parse()
+        .ok()
+        .filter(|speed: &f64| *speed > 0.0 && speed.is_finite())
+        .ok_or_else(|| format!("Valor inválido para {}: {}", flag, value))
+}
+
+/// Returns the probability, between 0 and 1, that follows the given flag.
+fn parse_probability(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<f64, String> {
+    let value = next_value(args, flag)?;
+    value
+        .parse()
+        .ok()
+        .filter(|probability| (0.0..=1.0).contains(probability))
+        .ok_or_else(|| format!("Valor inválido para {}: {}", flag, value))
+}
+
+/// Returns the single ASCII character given for the flag.
+fn parse_character(value: &str, flag: &str) -> Result<u8, String> {
+    match value.as_bytes() {
+        [character] if character.is_ascii() => Ok(*character),
+        _ => Err(format!(
+            "Valor inválido para {}: {} (se esperaba un único carácter)",
+            flag, value
+        )),
+    }
+}
+
+/// Returns the value that follows the given flag.
+fn next_value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("Falta el valor de {}", flag))
+}
@@ -1,18 +1,69 @@
 /// Maximum amount of dispensers a coffee machine can have
 pub const MAX_DISPENSERS: u64 = 4;
 
+/// Time (in milliseconds) between each decision of the autoscaler
+pub const AUTOSCALE_CHECK_INTERVAL: u64 = 1000;
+/// Orders waiting per active dispenser above which the autoscaler adds a dispenser
+pub const AUTOSCALE_ORDERS_PER_DISPENSER: usize = 2;
+/// Time (in milliseconds) the oldest order may wait in the queue before the autoscaler adds a dispenser
+pub const AUTOSCALE_MAX_WAIT: u64 = 5000;
+/// Time (in milliseconds) the queue must stay empty before the autoscaler retires a dispenser
+pub const AUTOSCALE_SCALE_DOWN_COOLDOWN: u64 = 5000;
+
 /// Maximum amount (capacity) of a milk foam container
 pub const INITIAL_MILK_FOAM: u64 = 100;
 /// Maximum amount (capacity) of a ground coffee beans container
 pub const INITIAL_GROUND_COFFEE_BEANS: u64 = 100;
 
-/// Custom resource alert factor
+/// Custom resource alert factor, used by default as the fraction of the capacity under which a warning alert is raised
 pub const RESOURCE_ALERT_FACTOR: f64 = 0.2;
-/// Coffee beans alert threshold, used to trigger a console alert when the amount of coffee beans is below this threshold
-pub const COFFEE_BEANS_ALERT_THRESHOLD: f64 =
-    INITIAL_GROUND_COFFEE_BEANS as f64 * RESOURCE_ALERT_FACTOR;
-/// Milk foam alert threshold, used to trigger an alert when the amount of milk foam is below this threshold
-pub const MILK_FOAM_ALERT_THRESHOLD: f64 = INITIAL_MILK_FOAM as f64 * RESOURCE_ALERT_FACTOR;
+/// Fraction of the capacity under which a critical alert is raised, unless configured otherwise
+pub const CRITICAL_ALERT_FACTOR: f64 = 0.05;
+/// Amount under which a container is considered empty, unless configured otherwise
+pub const EMPTY_ALERT_AMOUNT: u64 = 1;
+/// Margin above a threshold, as a factor of the capacity, the level has to reach for an alert of that severity to be cleared
+pub const ALERT_HYSTERESIS_FACTOR: f64 = 0.1;
+/// Time (in milliseconds) between reminders of a low level alert that is not cleared yet. `None` disables reminders
+pub const DEFAULT_ALERT_REMINDER_INTERVAL: Option<u64> = None;
+
+/// Times the delivery of an alert to a sink is retried after a failure
+pub const ALERT_SINK_MAX_RETRIES: u32 = 3;
+/// Time (in milliseconds) before the first retry of an alert delivery. It doubles on each retry
+pub const ALERT_SINK_RETRY_DELAY: u64 = 100;
+/// Time (in milliseconds) to connect, send and receive the response of an alert webhook
+pub const ALERT_WEBHOOK_TIMEOUT: u64 = 2000;
+/// Time (in seconds) of the window in which the rate limit of the alert sinks applies
+pub const ALERT_RATE_LIMIT_WINDOW: u64 = 60;
+/// Maximum alerts delivered to each sink per rate limit window. `None` disables the limit
+pub const DEFAULT_ALERT_RATE_LIMIT: Option<usize> = None;
+
+/// Time (in seconds) of consumption taken into account to estimate when a container runs out
+pub const FORECAST_WINDOW: u64 = 30;
+/// Time (in milliseconds) between each update of the depletion forecasts
+pub const FORECAST_UPDATE_INTERVAL: u64 = 1000;
+/// Time (in seconds) below which an estimated depletion raises an alert. `None` disables these alerts
+pub const DEFAULT_FORECAST_HORIZON: Option<u64> = None;
+
+/// Time (in milliseconds) between each check of the delivery scheduler for a shutdown while waiting for the next delivery
+pub const DELIVERY_CHECK_INTERVAL: u64 = 100;
+
+/// Time (in milliseconds) the mock supplier takes to deliver an order, unless configured otherwise
+pub const DEFAULT_SUPPLIER_LEAD_TIME: u64 = 5000;
+/// Minimum fraction of a purchase order the mock supplier delivers when it fulfils it partially
+pub const MOCK_SUPPLIER_MIN_PARTIAL_FILL: f64 = 0.25;
+
+/// Maximum amount (capacity) of the small cups container. Cups are only replenished by deliveries
+pub const INITIAL_SMALL_CUPS: u64 = 50;
+/// Maximum amount (capacity) of the medium cups container, the size of the orders that do not say otherwise
+pub const INITIAL_MEDIUM_CUPS: u64 = 100;
+/// Maximum amount (capacity) of the large cups container
+pub const INITIAL_LARGE_CUPS: u64 = 50;
+/// Factor applied to the amounts of the recipes of the menu, which are for medium drinks, for small drinks
+pub const SMALL_SIZE_FACTOR: f64 = 0.75;
+/// Factor applied to the amounts of the recipes of the menu, which are for medium drinks, for large drinks
+pub const LARGE_SIZE_FACTOR: f64 = 1.5;
+/// Time (in milliseconds) a dispenser waits for a delivery of cups when there are none of the size of the order and the customer has no deadline
+pub const CUP_WAIT_TIMEOUT: u64 = 5000;
 
 /// Maximum amount (capacity) of a coffee beans to grind container. This value should be high enough to handle all possible drink order-examples in case of refill
 pub const INITIAL_COFFEE_BEANS_TO_GRIND: u64 = 100000;
@@ -32,7 +83,42 @@ pub const BASE_TIME_RESOURCE_REFILL: u64 = (0.10 * BASE_TIME_RESOURCE_APPLICATIO
 /// Time (in seconds) between each coffee machine status update
 pub const STATS_UPDATE_INTERVAL: u64 = 5;
 /// Time (in milliseconds) between each order taken. This is used to simulate the arrival of a customer.
+/// Only for the orders without an arrival time, unless the arrivals are Poisson
 pub const ORDER_TIME_INTERVAL_ARRIVAL: u64 = 2000;
+/// Maximum time (in milliseconds) the order reader sleeps while waiting for the next arrival before checking if it has to stop
+pub const ARRIVAL_CHECK_INTERVAL: u64 = 100;
+/// Time (in milliseconds) a customer waits for its drink when the order does not say otherwise. `None` means customers wait forever
+pub const DEFAULT_ORDER_PATIENCE: Option<u64> = None;
+
+/// Time (in milliseconds) the dispensers have to finish the drinks in preparation after a termination signal, unless configured otherwise
+pub const DEFAULT_DRAIN_TIMEOUT: u64 = 10000;
+/// File where the orders nobody started to prepare are saved after a termination signal, unless configured otherwise
+pub const DEFAULT_UNSTARTED_ORDERS_FILE: &str = "unstarted_orders.csv";
+/// Time (in milliseconds) between each check for finished or crashed dispensers while waiting for them
+pub const DISPENSER_JOIN_INTERVAL: u64 = 50;
+/// Times a crashed dispenser is restarted before leaving it out of service, unless configured otherwise
+pub const DEFAULT_MAX_DISPENSER_RESTARTS: u64 = 3;
+/// Times an order can make a dispenser crash before it is discarded instead of re-queued
+pub const MAX_ORDER_CRASHES: u32 = 2;
+
+/// Scheduling policy used by the dispensers when none is given in the command line
+pub const DEFAULT_SCHEDULING_POLICY: &str = "fifo";
+
+/// What the reader does with the rows that are not valid orders, unless configured otherwise
+pub const DEFAULT_INVALID_ORDER_POLICY: &str = "stop";
+/// File where the quarantined rows are written, unless configured otherwise
+pub const DEFAULT_REJECTS_FILE: &str = "rejected_orders.csv";
+/// Character that separates the columns of the orders file, unless configured otherwise
+pub const DEFAULT_DELIMITER: u8 = b',';
+/// Character that starts the comment lines of the orders file, unless configured otherwise. `None` means there are no comments
+pub const DEFAULT_COMMENT: Option<u8> = Some(b'#');
+
+/// First argument that runs the order generator instead of the coffee machine
+pub const GENERATE_COMMAND: &str = "generate";
+/// Orders the generator writes, unless configured otherwise
+pub const DEFAULT_GENERATED_ORDERS: u64 = 100;
+/// Maximum amount of each ingredient of the generated custom drinks, unless configured otherwise
+pub const MAX_GENERATED_AMOUNT: u64 = 10;
 
 /// Color for the console output, according to the actor involved
 pub const COLOR_RED: &str = "\x1b[31m";
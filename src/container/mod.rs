@@ -1,3 +1,111 @@
+mod monitor;
+
+use crate::constants::{
+    INITIAL_COFFEE_BEANS_TO_GRIND, INITIAL_COLD_MILK, INITIAL_GROUND_COFFEE_BEANS,
+    INITIAL_LARGE_CUPS, INITIAL_MEDIUM_CUPS, INITIAL_MILK_FOAM, INITIAL_SMALL_CUPS,
+};
+pub use monitor::{ContainerMonitor, Signal};
+use serde::{Serialize, Serializer};
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Identifies each of the containers of the coffee machine.
+pub enum ContainerKind {
+    GroundCoffee = 0,
+    MilkFoam,
+    CoffeeBeans,
+    ColdMilk,
+    SmallCups,
+    MediumCups,
+    LargeCups,
+}
+
+impl ContainerKind {
+    /// Amount of containers of the coffee machine.
+    pub const COUNT: usize = 7;
+
+    /// Every container, in the order they are reported.
+    pub const ALL: [ContainerKind; Self::COUNT] = [
+        ContainerKind::GroundCoffee,
+        ContainerKind::MilkFoam,
+        ContainerKind::CoffeeBeans,
+        ContainerKind::ColdMilk,
+        ContainerKind::SmallCups,
+        ContainerKind::MediumCups,
+        ContainerKind::LargeCups,
+    ];
+
+    /// Returns the name used to refer to the container in the command line and in files.
+    pub fn id(&self) -> &'static str {
+        match self {
+            ContainerKind::GroundCoffee => "ground-coffee",
+            ContainerKind::MilkFoam => "milk-foam",
+            ContainerKind::CoffeeBeans => "coffee-beans",
+            ContainerKind::ColdMilk => "cold-milk",
+            ContainerKind::SmallCups => "small-cups",
+            ContainerKind::MediumCups => "medium-cups",
+            ContainerKind::LargeCups => "large-cups",
+        }
+    }
+
+    /// Returns True if the container holds a raw material or cups, which are only replenished
+    /// by deliveries.
+    pub fn is_raw(&self) -> bool {
+        !matches!(self, ContainerKind::GroundCoffee | ContainerKind::MilkFoam)
+    }
+
+    /// Returns the maximum amount the container can hold.
+    pub fn capacity(&self) -> u64 {
+        match self {
+            ContainerKind::GroundCoffee => INITIAL_GROUND_COFFEE_BEANS,
+            ContainerKind::MilkFoam => INITIAL_MILK_FOAM,
+            ContainerKind::CoffeeBeans => INITIAL_COFFEE_BEANS_TO_GRIND,
+            ContainerKind::ColdMilk => INITIAL_COLD_MILK,
+            ContainerKind::SmallCups => INITIAL_SMALL_CUPS,
+            ContainerKind::MediumCups => INITIAL_MEDIUM_CUPS,
+            ContainerKind::LargeCups => INITIAL_LARGE_CUPS,
+        }
+    }
+}
+
+impl FromStr for ContainerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ContainerKind::ALL
+            .into_iter()
+            .find(|kind| kind.id() == s)
+            .ok_or_else(|| {
+                format!(
+                    "Contenedor desconocido: {} (opciones: ground-coffee, milk-foam, coffee-beans, cold-milk, small-cups, medium-cups, large-cups)",
+                    s
+                )
+            })
+    }
+}
+
+impl Serialize for ContainerKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl Display for ContainerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ContainerKind::GroundCoffee => "café molido",
+            ContainerKind::MilkFoam => "leche espumada",
+            ContainerKind::CoffeeBeans => "granos de café para moler",
+            ContainerKind::ColdMilk => "leche fría",
+            ContainerKind::SmallCups => "vasos chicos",
+            ContainerKind::MediumCups => "vasos medianos",
+            ContainerKind::LargeCups => "vasos grandes",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 /// Represents a container with a certain current amount and amount used.
 pub struct Container {
     current_amount: u64,
@@ -25,6 +133,12 @@ impl Container {
         self.amount_used += amount;
     }
 
+    /// Puts back an amount taken from the container that was not used after all.
+    pub fn put_back(&mut self, amount: &u64) {
+        self.current_amount += amount;
+        self.amount_used -= amount;
+    }
+
     /// Returns if the container has the current amount.
     pub fn has_enough(&self, amount: &u64) -> bool {
         self.current_amount >= *amount
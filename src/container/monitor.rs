@@ -0,0 +1,298 @@
+use crate::alert::{AlertLevels, Severity};
+use crate::container::Container;
+use crate::stats::WakeupStats;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug)]
+/// Conditions a thread can wait for on a monitored container.
+pub enum Signal {
+    /// The container is empty and has to be refilled
+    RefillNeeded = 0,
+    /// The severity of the low level alert of the container changed
+    AlertLevelChanged,
+    /// The container has just been refilled
+    RefillCompleted,
+}
+
+impl Signal {
+    /// Every signal, in the order they are reported.
+    pub const ALL: [Signal; 3] = [
+        Signal::RefillNeeded,
+        Signal::AlertLevelChanged,
+        Signal::RefillCompleted,
+    ];
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Signal::RefillNeeded => "refill necesario",
+            Signal::AlertLevelChanged => "cambio de nivel de alerta",
+            Signal::RefillCompleted => "refill completado",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Container protected by a mutex, with a condition variable per signal.
+///
+/// Only the threads interested in a signal are woken up when it happens, instead of every
+/// thread waiting on the container. With `shared_signalling` every signal uses the same
+/// condition variable and every change wakes everyone up, which is useful to compare both
+/// approaches.
+pub struct ContainerMonitor {
+    container: Mutex<Container>,
+    levels: AlertLevels,
+    severity: AtomicU8,
+    shared_signalling: bool,
+    conditions: [Condvar; 3],
+    wakeups: [WakeupStats; 3],
+    poison_recoveries: AtomicU64,
+}
+
+impl ContainerMonitor {
+    /// Creates a monitored container with the given initial amount and alert levels.
+    pub fn new(initial_amount: u64, levels: AlertLevels, shared_signalling: bool) -> Self {
+        let severity = levels.severity(initial_amount, None);
+        Self {
+            container: Mutex::new(Container::new(initial_amount)),
+            levels,
+            severity: AtomicU8::new(encode_severity(severity)),
+            shared_signalling,
+            conditions: [Condvar::new(), Condvar::new(), Condvar::new()],
+            wakeups: [WakeupStats::new(), WakeupStats::new(), WakeupStats::new()],
+            poison_recoveries: AtomicU64::new(0),
+        }
+    }
+
+    /// Locks the container.
+    /// If a thread panicked while holding the lock, the container is recovered as it was left.
+    pub fn lock(&self) -> MutexGuard<'_, Container> {
+        self.container
+            .lock()
+            .unwrap_or_else(|poisoned| self.recover(poisoned.into_inner()))
+    }
+
+    /// Clears the poison of the lock, left by a thread that panicked while holding it.
+    /// The amount of the container is kept, but the severity is computed again and every
+    /// waiting thread is woken up, since the notifications of the last change may be missing.
+    fn recover<'a>(&self, guard: MutexGuard<'a, Container>) -> MutexGuard<'a, Container> {
+        self.container.clear_poison();
+        self.poison_recoveries.fetch_add(1, Ordering::Relaxed);
+        self.update_severity(&guard);
+        self.notify_everyone();
+        guard
+    }
+
+    /// Returns how many times the lock was recovered after a panic.
+    pub fn get_poison_recoveries(&self) -> u64 {
+        self.poison_recoveries.load(Ordering::Relaxed)
+    }
+
+    /// Returns the amounts under which each severity is raised.
+    pub fn get_levels(&self) -> &AlertLevels {
+        &self.levels
+    }
+
+    /// Returns the severity of the low level alert of the container, if there is one.
+    /// It only changes while the container is locked, so it can be used in wait predicates.
+    pub fn get_severity(&self) -> Option<Severity> {
+        decode_severity(self.severity.load(Ordering::Relaxed))
+    }
+
+    /// Updates the severity of the alert after a change in the container.
+    /// Returns True if it changed.
+    fn update_severity(&self, container: &Container) -> bool {
+        let current = self.get_severity();
+        let severity = self
+            .levels
+            .severity(*container.get_current_amount(), current);
+        self.severity
+            .store(encode_severity(severity), Ordering::Relaxed);
+        severity != current
+    }
+
+    /// Returns the condition variable used for the given signal.
+    fn condition(&self, signal: Signal) -> &Condvar {
+        if self.shared_signalling {
+            &self.conditions[0]
+        } else {
+            &self.conditions[signal as usize]
+        }
+    }
+
+    /// Notifies the threads interested in the current level of the container.
+    /// Should be called after consuming from it.
+    pub fn notify_consumption(&self, container: &Container) {
+        let severity_changed = self.update_severity(container);
+        if self.shared_signalling {
+            self.conditions[0].notify_all();
+            return;
+        }
+        if !container.has_any() {
+            self.condition(Signal::RefillNeeded).notify_one();
+        }
+        if severity_changed {
+            self.condition(Signal::AlertLevelChanged).notify_all();
+        }
+    }
+
+    /// Notifies the threads waiting for the container to be refilled.
+    /// Should be called after adding to it.
+    pub fn notify_refill(&self, container: &Container) {
+        if self.update_severity(container) {
+            self.condition(Signal::AlertLevelChanged).notify_all();
+        }
+        self.condition(Signal::RefillCompleted).notify_all();
+    }
+
+    /// Wakes up every waiting thread, no matter the signal. Used on shutdown.
+    pub fn notify_everyone(&self) {
+        for condition in &self.conditions {
+            condition.notify_all();
+        }
+    }
+
+    /// Blocks while `keep_waiting` is true, waiting for the given signal.
+    /// Every wakeup is recorded as useful or spurious.
+    pub fn wait_while<'a>(
+        &self,
+        signal: Signal,
+        guard: MutexGuard<'a, Container>,
+        mut keep_waiting: impl FnMut(&mut Container) -> bool,
+    ) -> MutexGuard<'a, Container> {
+        let wakeups = &self.wakeups[signal as usize];
+        let mut woken_up = false;
+        self.condition(signal)
+            .wait_while(guard, |container| {
+                let keep = keep_waiting(container);
+                if woken_up {
+                    wakeups.record(!keep);
+                }
+                woken_up = true;
+                keep
+            })
+            .unwrap_or_else(|poisoned| self.recover(poisoned.into_inner()))
+    }
+
+    /// Like `wait_while`, but gives up after `timeout` if there is one.
+    /// Also returns True if it gave up because of the timeout.
+    pub fn wait_timeout_while<'a>(
+        &self,
+        signal: Signal,
+        guard: MutexGuard<'a, Container>,
+        timeout: Option<Duration>,
+        mut keep_waiting: impl FnMut(&mut Container) -> bool,
+    ) -> (MutexGuard<'a, Container>, bool) {
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return (self.wait_while(signal, guard, keep_waiting), false),
+        };
+        let wakeups = &self.wakeups[signal as usize];
+        let mut woken_up = false;
+        let (guard, result) = self
+            .condition(signal)
+            .wait_timeout_while(guard, timeout, |container| {
+                let keep = keep_waiting(container);
+                if woken_up {
+                    wakeups.record(!keep);
+                }
+                woken_up = true;
+                keep
+            })
+            .unwrap_or_else(|poisoned| {
+                let (guard, result) = poisoned.into_inner();
+                (self.recover(guard), result)
+            });
+        (guard, result.timed_out())
+    }
+
+    /// Returns the wakeups recorded for the given signal.
+    pub fn get_wakeups(&self, signal: Signal) -> &WakeupStats {
+        &self.wakeups[signal as usize]
+    }
+}
+
+/// Stores an optional severity in a single byte, where 0 means no alert.
+fn encode_severity(severity: Option<Severity>) -> u8 {
+    severity.map_or(0, |severity| severity as u8 + 1)
+}
+
+/// Reads a severity stored with `encode_severity`.
+fn decode_severity(value: u8) -> Option<Severity> {
+    value
+        .checked_sub(1)
+        .map(|index| Severity::ALL[index as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::AlertThresholds;
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    /// Default alert levels of a container of 100.
+    fn levels() -> AlertLevels {
+        AlertThresholds::default().resolve(100)
+    }
+
+    /// Waits on the container until a refill brings it back to `amount`.
+    fn wait_for_refill(monitor: &Arc<ContainerMonitor>, amount: u64) -> JoinHandle<()> {
+        let monitor = monitor.clone();
+        thread::spawn(move || {
+            let _refilled = monitor.wait_while(Signal::RefillCompleted, monitor.lock(), |level| {
+                !level.has_enough(&amount)
+            });
+        })
+    }
+
+    /// Consumes from the container until it is below its alert threshold, and then fills it.
+    fn consume_and_refill(monitor: &ContainerMonitor) {
+        let mut level = monitor.lock();
+        level.subtract(&60);
+        monitor.notify_consumption(&level);
+        drop(level);
+        thread::sleep(Duration::from_millis(50));
+        let mut level = monitor.lock();
+        let missing = 100 - *level.get_current_amount();
+        level.add(&missing);
+        monitor.notify_refill(&level);
+    }
+
+    #[test]
+    fn a_consumption_does_not_wake_up_the_threads_waiting_for_a_refill() {
+        let monitor = Arc::new(ContainerMonitor::new(100, levels(), false));
+        monitor.lock().subtract(&10);
+        let waiting = wait_for_refill(&monitor, 100);
+        thread::sleep(Duration::from_millis(50));
+
+        consume_and_refill(&monitor);
+        waiting.join().expect("Failed to wait for the refill");
+
+        assert_eq!(
+            monitor.get_wakeups(Signal::RefillCompleted).to_string(),
+            "útiles 1 - espurios 0"
+        );
+    }
+
+    #[test]
+    fn with_shared_signalling_every_change_wakes_up_every_thread() {
+        let monitor = Arc::new(ContainerMonitor::new(100, levels(), true));
+        monitor.lock().subtract(&10);
+        let waiting = wait_for_refill(&monitor, 100);
+        thread::sleep(Duration::from_millis(50));
+
+        consume_and_refill(&monitor);
+        waiting.join().expect("Failed to wait for the refill");
+
+        assert_eq!(
+            monitor.get_wakeups(Signal::RefillCompleted).to_string(),
+            "útiles 1 - espurios 1"
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::container::ContainerKind;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug)]
+/// Stock of a raw material that arrives to the machine at a given time.
+pub struct Delivery {
+    at: Duration,
+    container: ContainerKind,
+    amount: u64,
+    purchase_order: Option<u64>,
+}
+
+impl Delivery {
+    /// Creates a delivery of `amount` for the given container, arriving `at` after the machine
+    /// started.
+    pub fn new(at: Duration, container: ContainerKind, amount: u64) -> Self {
+        Self {
+            at,
+            container,
+            amount,
+            purchase_order: None,
+        }
+    }
+
+    /// Marks the delivery as the fulfilment of the purchase order with the given id.
+    pub fn for_purchase_order(mut self, id: u64) -> Self {
+        self.purchase_order = Some(id);
+        self
+    }
+
+    /// Returns the time since the start of the machine when the delivery arrives.
+    pub fn get_at(&self) -> Duration {
+        self.at
+    }
+
+    /// Returns the container that receives the delivery.
+    pub fn get_container(&self) -> ContainerKind {
+        self.container
+    }
+
+    /// Returns the amount delivered.
+    pub fn get_amount(&self) -> u64 {
+        self.amount
+    }
+
+    /// Returns the id of the purchase order this delivery fulfils, if any.
+    pub fn get_purchase_order(&self) -> Option<u64> {
+        self.purchase_order
+    }
+}
+
+/// Reads the deliveries of a file with a `<milliseconds>,<container>,<amount>` row per delivery,
+/// sorted by arrival time. Only raw material containers can receive deliveries.
+pub fn read_deliveries(path: &Path) -> Result<Vec<Delivery>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|error| format!("No se pudo leer {}: {}", path.display(), error))?;
+    let mut deliveries = Vec::new();
+    for (line, result) in reader.records().enumerate() {
+        let invalid =
+            |reason: String| format!("Entrega inválida en la línea {}: {}", line + 1, reason);
+        let record = result.map_err(|error| invalid(error.to_string()))?;
+        if record.len() != 3 {
+            return Err(invalid(String::from(
+                "se esperaba <milisegundos>,<contenedor>,<cantidad>",
+            )));
+        }
+        let at = record[0]
+            .parse()
+            .map_err(|_| invalid(format!("tiempo inválido: {}", &record[0])))?;
+        let container: ContainerKind = record[1].parse().map_err(invalid)?;
+        if !container.is_raw() {
+            return Err(invalid(format!(
+                "solo se pueden recibir entregas de materia prima o vasos, no de {}",
+                container.id()
+            )));
+        }
+        let amount = record[2]
+            .parse()
+            .map_err(|_| invalid(format!("cantidad inválida: {}", &record[2])))?;
+        deliveries.push(Delivery::new(Duration::from_millis(at), container, amount));
+    }
+    deliveries.sort_by_key(|delivery| delivery.at);
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process;
+
+    /// Reads the deliveries of a file with the given rows.
+    fn read(name: &str, rows: &str) -> Result<Vec<Delivery>, String> {
+        let path = std::env::temp_dir().join(format!("{}-{}.csv", name, process::id()));
+        fs::write(&path, rows).expect("Failed to write deliveries");
+        let deliveries = read_deliveries(&path);
+        fs::remove_file(&path).expect("Failed to remove deliveries");
+        deliveries
+    }
+
+    #[test]
+    fn deliveries_are_sorted_by_arrival_time() {
+        let deliveries = read(
+            "sorted-deliveries",
+            "5000, cold-milk, 300\n1000, coffee-beans, 200\n",
+        )
+        .expect("Failed to read deliveries");
+
+        assert_eq!(deliveries.len(), 2);
+        assert_eq!(deliveries[0].get_at(), Duration::from_secs(1));
+        assert_eq!(deliveries[0].get_container(), ContainerKind::CoffeeBeans);
+        assert_eq!(deliveries[0].get_amount(), 200);
+        assert_eq!(deliveries[1].get_container(), ContainerKind::ColdMilk);
+    }
+
+    #[test]
+    fn only_raw_material_containers_receive_deliveries() {
+        let error = read(
+            "processed-deliveries",
+            "1000,coffee-beans,200\n2000,milk-foam,100\n",
+        )
+        .unwrap_err();
+
+        assert!(error.starts_with("Entrega inválida en la línea 2"));
+    }
+}
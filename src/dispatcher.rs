@@ -0,0 +1,119 @@
+use crate::blocking_queue::BlockingQueue;
+use crate::dispenser::Dispenser;
+use crate::order::Order;
+use crate::scheduling_policy::{RefillStatus, SchedulingPolicy};
+use crate::utils::Message;
+use crate::work_stealing::WorkStealingQueue;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Hands the messages sent by the order readers to the dispensers.
+pub trait Dispatcher: Send + Sync {
+    /// Registers a dispenser that will take messages from the dispatcher.
+    fn register(&self, dispenser: &Dispenser);
+
+    /// Unregisters a retired dispenser, handing its pending orders to the rest.
+    fn unregister(&self, dispenser: &Dispenser);
+
+    /// Sends a message to the dispensers.
+    fn push(&self, message: Message);
+
+    /// Takes the next message for the given dispenser, blocking until there is one.
+    fn pop(
+        &self,
+        dispenser: &Dispenser,
+        policy: &dyn SchedulingPolicy,
+        refill_status: &RefillStatus,
+    ) -> Message;
+
+    /// Returns the orders that are waiting to be taken by a dispenser, except the expired ones.
+    /// Those are only discarded once a dispenser takes them, but their customers already left,
+    /// so they are no longer demand.
+    fn waiting_orders(&self) -> Vec<Order>;
+
+    /// Removes and returns every message left.
+    fn drain(&self) -> Vec<Message>;
+}
+
+/// Every dispenser takes its messages from a single shared queue.
+impl Dispatcher for BlockingQueue<Message> {
+    fn register(&self, _dispenser: &Dispenser) {}
+
+    fn unregister(&self, _dispenser: &Dispenser) {}
+
+    fn push(&self, message: Message) {
+        self.push_back(message);
+    }
+
+    fn pop(
+        &self,
+        dispenser: &Dispenser,
+        policy: &dyn SchedulingPolicy,
+        refill_status: &RefillStatus,
+    ) -> Message {
+        self.pop_by(|queue| policy.select(queue, refill_status, dispenser))
+    }
+
+    fn waiting_orders(&self) -> Vec<Order> {
+        self.inspect(|queue| {
+            queue
+                .iter()
+                .filter_map(|message| match message {
+                    Message::Job(order) if !order.is_expired() => Some(order.clone()),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    fn drain(&self) -> Vec<Message> {
+        BlockingQueue::drain(self)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Available dispatchers, selectable from the command line.
+pub enum DispatcherKind {
+    SharedQueue,
+    WorkStealing,
+}
+
+impl DispatcherKind {
+    /// Every available dispatcher, in the order they are shown in benchmarks.
+    pub const ALL: [DispatcherKind; 2] =
+        [DispatcherKind::SharedQueue, DispatcherKind::WorkStealing];
+
+    /// Creates the dispatcher represented by this kind.
+    pub fn build(&self) -> Arc<dyn Dispatcher> {
+        match self {
+            DispatcherKind::SharedQueue => Arc::new(BlockingQueue::new()),
+            DispatcherKind::WorkStealing => Arc::new(WorkStealingQueue::new()),
+        }
+    }
+}
+
+impl FromStr for DispatcherKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shared" => Ok(DispatcherKind::SharedQueue),
+            "work-stealing" => Ok(DispatcherKind::WorkStealing),
+            _ => Err(format!(
+                "Dispatcher desconocido: {} (opciones: shared, work-stealing)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for DispatcherKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DispatcherKind::SharedQueue => "shared",
+            DispatcherKind::WorkStealing => "work-stealing",
+        };
+        write!(f, "{}", name)
+    }
+}
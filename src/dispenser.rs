@@ -0,0 +1,205 @@
+use crate::order::Order;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug)]
+/// Ingredients a dispenser is able to apply.
+pub struct Capabilities {
+    coffee: bool,
+    milk: bool,
+    water: bool,
+}
+
+impl Capabilities {
+    /// Capabilities of a dispenser that can apply every ingredient.
+    pub fn all() -> Self {
+        Self {
+            coffee: true,
+            milk: true,
+            water: true,
+        }
+    }
+
+    /// Returns True if the other capabilities include every ingredient of these, so that the
+    /// other dispenser can make every order this one can.
+    pub fn covered_by(&self, other: &Capabilities) -> bool {
+        (other.coffee || !self.coffee) && (other.milk || !self.milk) && (other.water || !self.water)
+    }
+
+    /// Returns True if every ingredient required by the order can be applied.
+    pub fn can_make(&self, order: &Order) -> bool {
+        (self.coffee || !order.requires_coffee())
+            && (self.milk || !order.requires_milk())
+            && (self.water || !order.requires_water())
+    }
+}
+
+impl FromStr for Capabilities {
+    type Err = String;
+
+    /// Accepts `all`, `water-only`, `no-milk`, `no-coffee` or a list of ingredients
+    /// separated by `+` (for example `coffee+water`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => return Ok(Self::all()),
+            "water-only" => return "water".parse(),
+            "no-milk" => return "coffee+water".parse(),
+            "no-coffee" => return "milk+water".parse(),
+            _ => {}
+        }
+        let mut capabilities = Self {
+            coffee: false,
+            milk: false,
+            water: false,
+        };
+        for ingredient in s.split('+') {
+            match ingredient {
+                "coffee" => capabilities.coffee = true,
+                "milk" => capabilities.milk = true,
+                "water" => capabilities.water = true,
+                _ => return Err(format!("Ingrediente desconocido: {}", ingredient)),
+            }
+        }
+        Ok(capabilities)
+    }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ingredients: Vec<&str> = [
+            (self.coffee, "coffee"),
+            (self.milk, "milk"),
+            (self.water, "water"),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, name)| *name)
+        .collect();
+        write!(f, "{}", ingredients.join("+"))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Describes the hardware of a dispenser: what it can apply and how fast it does it.
+pub struct DispenserSpec {
+    capabilities: Capabilities,
+    speed: f64,
+}
+
+impl DispenserSpec {
+    /// Creates a new spec. A speed of 2.0 applies ingredients twice as fast as the base time.
+    pub fn new(capabilities: Capabilities, speed: f64) -> Self {
+        Self {
+            capabilities,
+            speed,
+        }
+    }
+}
+
+impl Default for DispenserSpec {
+    fn default() -> Self {
+        Self::new(Capabilities::all(), 1.0)
+    }
+}
+
+impl FromStr for DispenserSpec {
+    type Err = String;
+
+    /// Accepts `<capabilities>[:<speed>]`, for example `no-milk:1.5` or `water`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (capabilities, speed) = match s.split_once(':') {
+            Some((capabilities, speed)) => (
+                capabilities,
+                speed
+                    .parse::<f64>()
+                    .map_err(|_| format!("Velocidad inválida: {}", speed))?,
+            ),
+            None => (s, 1.0),
+        };
+        if !(speed > 0.0 && speed.is_finite()) {
+            return Err(format!(
+                "La velocidad debe ser un número positivo: {}",
+                speed
+            ));
+        }
+        Ok(Self::new(capabilities.parse()?, speed))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// A dispenser of the coffee machine, identified by its number.
+pub struct Dispenser {
+    id: u64,
+    spec: DispenserSpec,
+}
+
+impl Dispenser {
+    /// Creates a dispenser with the given number and hardware.
+    pub fn new(id: u64, spec: DispenserSpec) -> Self {
+        Self { id, spec }
+    }
+
+    /// Returns the number of the dispenser.
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns what the dispenser is able to apply.
+    pub fn get_capabilities(&self) -> &Capabilities {
+        &self.spec.capabilities
+    }
+
+    /// Returns the time this dispenser takes to apply the given base time worth of work.
+    pub fn application_time(&self, base_millis: u64) -> Duration {
+        Duration::from_secs_f64(base_millis as f64 / 1000.0 / self.spec.speed)
+    }
+}
+
+impl Display for Dispenser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Dispenser {} [{} x{:.1}]",
+            self.id, self.spec.capabilities, self.spec.speed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_dispenser_can_only_make_the_orders_whose_ingredients_it_applies() {
+        let no_milk: Capabilities = "no-milk".parse().unwrap();
+
+        assert!(no_milk.can_make(&Order::new(2, 0, 1, None)));
+        assert!(no_milk.can_make(&Order::new(0, 0, 0, None)));
+        assert!(!no_milk.can_make(&Order::new(2, 1, 1, None)));
+        assert!(Capabilities::all().can_make(&Order::new(2, 1, 1, None)));
+    }
+
+    #[test]
+    fn capabilities_are_parsed_from_presets_and_ingredient_lists() {
+        let capabilities: Capabilities = "coffee+water".parse().unwrap();
+        assert_eq!(capabilities.to_string(), "coffee+water");
+        assert_eq!(
+            "no-coffee".parse::<Capabilities>().unwrap().to_string(),
+            "milk+water"
+        );
+        assert!("coffee+tea".parse::<Capabilities>().is_err());
+    }
+
+    #[test]
+    fn a_dispenser_is_covered_by_another_that_applies_at_least_the_same_ingredients() {
+        let water_only: Capabilities = "water-only".parse().unwrap();
+        let no_milk: Capabilities = "no-milk".parse().unwrap();
+
+        assert!(water_only.covered_by(&no_milk));
+        assert!(no_milk.covered_by(&Capabilities::all()));
+        assert!(no_milk.covered_by(&no_milk));
+        assert!(!no_milk.covered_by(&water_only));
+        assert!(!Capabilities::all().covered_by(&no_milk));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::constants::FORECAST_WINDOW;
+use crate::container::ContainerKind;
+use crate::order::Order;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Consumption rate of a container, estimated from the amount used during the last
+/// `FORECAST_WINDOW` seconds.
+pub struct ConsumptionRate {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl ConsumptionRate {
+    /// Creates a rate without samples.
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records the total amount used of the container at the given time, forgetting the
+    /// samples that are out of the window.
+    pub fn record(&mut self, now: Instant, amount_used: u64) {
+        let window = Duration::from_secs(FORECAST_WINDOW);
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > window)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, amount_used));
+    }
+
+    /// Returns the amount used per second during the window.
+    pub fn per_second(&self) -> f64 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some((first_time, first_used)), Some((last_time, last_used))) => {
+                let elapsed = last_time.duration_since(*first_time).as_secs_f64();
+                if elapsed == 0.0 {
+                    return 0.0;
+                }
+                (last_used - first_used) as f64 / elapsed
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Estimates the time until a container runs out.
+///
+/// The queued demand is considered as already consumed, and what is left is consumed at the
+/// recent rate. Returns zero if the queued orders already need everything that is left, and
+/// `None` if nothing is being consumed.
+pub fn estimate_depletion(stock: u64, queued_demand: u64, rate: f64) -> Option<Duration> {
+    if queued_demand >= stock {
+        return Some(Duration::ZERO);
+    }
+    if rate <= 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(
+        (stock - queued_demand) as f64 / rate,
+    ))
+}
+
+/// Returns the amount the waiting orders need from each container, given the current amount
+/// of the ground coffee and milk foam containers. Raw materials are only needed for the part
+/// of the demand the ground coffee and milk foam containers cannot serve. Each order needs a
+/// cup of its size.
+pub fn queued_demand(
+    orders: &[Order],
+    ground_coffee: u64,
+    milk_foam: u64,
+) -> [u64; ContainerKind::COUNT] {
+    let coffee: u64 = orders.iter().map(|order| order.get_coffee()).sum();
+    let milk: u64 = orders.iter().map(|order| order.get_milk()).sum();
+    let mut demand = [0; ContainerKind::COUNT];
+    demand[ContainerKind::GroundCoffee as usize] = coffee;
+    demand[ContainerKind::MilkFoam as usize] = milk;
+    demand[ContainerKind::CoffeeBeans as usize] = coffee.saturating_sub(ground_coffee);
+    demand[ContainerKind::ColdMilk as usize] = milk.saturating_sub(milk_foam);
+    for order in orders {
+        demand[order.get_size().cups() as usize] += 1;
+    }
+    demand
+}
+
+/// Depletion forecast of a container.
+pub struct Forecast {
+    rate: ConsumptionRate,
+    eta: Option<Duration>,
+}
+
+impl Forecast {
+    /// Creates a forecast without samples, where the container is not being consumed.
+    pub fn new() -> Self {
+        Self {
+            rate: ConsumptionRate::new(),
+            eta: None,
+        }
+    }
+
+    /// Records the current state of the container and returns the new estimated time until
+    /// it runs out.
+    pub fn update(
+        &mut self,
+        now: Instant,
+        stock: u64,
+        amount_used: u64,
+        queued_demand: u64,
+    ) -> Option<Duration> {
+        self.rate.record(now, amount_used);
+        self.eta = estimate_depletion(stock, queued_demand, self.rate.per_second());
+        self.eta
+    }
+
+    /// Returns the recent consumption per second of the container.
+    pub fn get_rate(&self) -> f64 {
+        self.rate.per_second()
+    }
+
+    /// Returns the last estimated time until the container runs out.
+    pub fn get_eta(&self) -> Option<Duration> {
+        self.eta
+    }
+}
+
+/// Formats an estimated time until a container runs out, for the reports.
+pub fn format_eta(eta: Option<Duration>) -> String {
+    match eta {
+        Some(eta) => format!("{:.1}s", eta.as_secs_f64()),
+        None => String::from("sin consumo"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_queued_demand_is_consumed_first_and_the_rest_at_the_recent_rate() {
+        assert_eq!(
+            estimate_depletion(100, 20, 4.0),
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(estimate_depletion(100, 120, 4.0), Some(Duration::ZERO));
+        assert_eq!(estimate_depletion(100, 20, 0.0), None);
+    }
+
+    #[test]
+    fn the_rate_only_considers_the_consumption_within_the_window() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut rate = ConsumptionRate::new();
+
+        rate.record(at(0), 0);
+        rate.record(at(FORECAST_WINDOW + 1), 100);
+        assert_eq!(rate.per_second(), 0.0);
+
+        rate.record(at(FORECAST_WINDOW + 11), 150);
+        assert_eq!(rate.per_second(), 5.0);
+    }
+
+    #[test]
+    fn the_forecast_estimates_the_depletion_from_the_recorded_consumption() {
+        let start = Instant::now();
+        let mut forecast = Forecast::new();
+
+        assert_eq!(forecast.update(start, 1000, 0, 0), None);
+        let eta = forecast.update(start + Duration::from_secs(10), 850, 150, 100);
+
+        assert_eq!(eta, Some(Duration::from_secs(50)));
+        assert_eq!(forecast.get_eta(), eta);
+    }
+
+    #[test]
+    fn raw_materials_are_only_needed_for_what_the_processed_containers_cannot_serve() {
+        let orders = [Order::new(10, 5, 1, None), Order::new(20, 0, 1, None)];
+
+        let demand = queued_demand(&orders, 25, 10);
+
+        assert_eq!(demand[ContainerKind::GroundCoffee as usize], 30);
+        assert_eq!(demand[ContainerKind::MilkFoam as usize], 5);
+        assert_eq!(demand[ContainerKind::CoffeeBeans as usize], 5);
+        assert_eq!(demand[ContainerKind::ColdMilk as usize], 0);
+    }
+}
@@ -0,0 +1,471 @@
+use crate::arrival::{poisson_gap, ArrivalProcess};
+use crate::config::GeneratorConfig;
+use crate::order::Size;
+use crate::order_source::InputFormat;
+use crate::utils::Resource;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde_json::{Map, Value};
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Name of the drink of the mix whose amounts are drawn from the distributions, instead of
+/// taken from the menu.
+pub const CUSTOM_DRINK: &str = "custom";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Distribution an amount of the generated orders is drawn from.
+pub enum AmountDistribution {
+    /// Always the same amount
+    Constant(u64),
+    /// Any amount between the two, both included, with the same probability
+    Uniform(u64, u64),
+    /// Poisson distribution with the given mean
+    Poisson(f64),
+}
+
+impl AmountDistribution {
+    /// Draws an amount.
+    fn sample(&self, rng: &mut impl Rng) -> u64 {
+        match *self {
+            AmountDistribution::Constant(amount) => amount,
+            AmountDistribution::Uniform(min, max) => rng.gen_range(min..=max),
+            AmountDistribution::Poisson(mean) => {
+                // Método de Knuth: se multiplican uniformes hasta quedar debajo de e^-media.
+                let limit = (-mean).exp();
+                let mut amount = 0;
+                let mut product = rng.gen::<f64>();
+                while product > limit {
+                    amount += 1;
+                    product *= rng.gen::<f64>();
+                }
+                amount
+            }
+        }
+    }
+}
+
+impl FromStr for AmountDistribution {
+    type Err = String;
+
+    /// Accepts an amount (`2`), a range (`0-10`) or a Poisson mean (`poisson:3.5`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Distribución inválida: {} (se esperaba <cantidad>, <mínimo>-<máximo> o poisson:<media>)",
+                s
+            )
+        };
+        if let Some(mean) = s.strip_prefix("poisson:") {
+            let mean: f64 = mean.trim().parse().map_err(|_| invalid())?;
+            // Con medias más grandes e^-media no se puede representar.
+            if !(0.0..=700.0).contains(&mean) {
+                return Err(invalid());
+            }
+            return Ok(AmountDistribution::Poisson(mean));
+        }
+        match s.split_once('-') {
+            Some((min, max)) => {
+                let min: u64 = min.trim().parse().map_err(|_| invalid())?;
+                let max: u64 = max.trim().parse().map_err(|_| invalid())?;
+                if min > max {
+                    return Err(invalid());
+                }
+                Ok(AmountDistribution::Uniform(min, max))
+            }
+            None => Ok(AmountDistribution::Constant(
+                s.trim().parse().map_err(|_| invalid())?,
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Options to choose from, each with its weight.
+pub struct Mix<T>(Vec<(T, f64)>);
+
+impl<T> Mix<T> {
+    /// Creates a mix where every option has the same weight.
+    pub fn uniform(options: impl IntoIterator<Item = T>) -> Self {
+        Self(options.into_iter().map(|option| (option, 1.0)).collect())
+    }
+
+    /// Returns the options of the mix.
+    pub fn options(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().map(|(option, _)| option)
+    }
+
+    /// Chooses an option with a probability proportional to its weight.
+    fn choose(&self, rng: &mut impl Rng) -> &T {
+        let total: f64 = self.0.iter().map(|(_, weight)| weight).sum();
+        let mut target = rng.gen::<f64>() * total;
+        for (option, weight) in &self.0 {
+            if target < *weight {
+                return option;
+            }
+            target -= weight;
+        }
+        // Por redondeo puede sobrar un resto: se elige la última opción con peso.
+        self.0
+            .iter()
+            .rev()
+            .find(|(_, weight)| *weight > 0.0)
+            .map(|(option, _)| option)
+            .expect("Failed to choose from an empty mix")
+    }
+}
+
+impl<T: FromStr> FromStr for Mix<T>
+where
+    T::Err: Display,
+{
+    type Err = String;
+
+    /// Accepts a `<option>:<weight>` list separated by commas, like `latte:3,espresso:1`. An
+    /// option without weight weighs 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Mezcla inválida: {}", s);
+        let mut mix = Vec::new();
+        for segment in s.split(',') {
+            let (option, weight) = match segment.rsplit_once(':') {
+                Some((option, weight)) => (option, weight.trim().parse().map_err(|_| invalid())?),
+                None => (segment, 1.0),
+            };
+            if weight < 0.0 || !f64::is_finite(weight) {
+                return Err(invalid());
+            }
+            let option = option
+                .trim()
+                .parse()
+                .map_err(|error| format!("Opción desconocida en la mezcla {}: {}", s, error))?;
+            mix.push((option, weight));
+        }
+        if mix.iter().all(|(_, weight)| *weight == 0.0) {
+            return Err(format!(
+                "Mezcla inválida: {} (alguna opción debe tener peso positivo)",
+                s
+            ));
+        }
+        Ok(Self(mix))
+    }
+}
+
+/// Fields of a generated order. Empty ones are left out of the output.
+struct GeneratedOrder {
+    id: u64,
+    drink: Option<String>,
+    size: Option<Size>,
+    amounts: Option<[u64; 3]>,
+    patience: Option<u64>,
+    arrival_time: Option<Duration>,
+}
+
+/// Draws the orders described by the configuration, the same ones for the same seed.
+struct Generator<'a> {
+    config: &'a GeneratorConfig,
+    rng: ChaCha8Rng,
+    /// Arrival time of the last order
+    elapsed: Duration,
+}
+
+impl<'a> Generator<'a> {
+    fn new(config: &'a GeneratorConfig) -> Self {
+        Self {
+            config,
+            rng: match config.seed {
+                Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+                None => ChaCha8Rng::from_entropy(),
+            },
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Draws the order with the given id. Drinks of the menu take their amounts from it.
+    fn next_order(&mut self, id: u64) -> GeneratedOrder {
+        let drink = self.config.drinks.choose(&mut self.rng).clone();
+        let size = self
+            .config
+            .sizes
+            .as_ref()
+            .map(|sizes| *sizes.choose(&mut self.rng));
+        let (drink, amounts) = if drink == CUSTOM_DRINK {
+            let amounts = Resource::ALL
+                .map(|ingredient| self.config.amounts[ingredient as usize].sample(&mut self.rng));
+            (None, Some(amounts))
+        } else {
+            (Some(drink), None)
+        };
+        let patience = self
+            .config
+            .patience
+            .map(|patience| patience.sample(&mut self.rng));
+        let arrival_time = match &self.config.arrival_process {
+            None => None,
+            Some(ArrivalProcess::Fixed(interval)) => {
+                if id > 1 {
+                    self.elapsed += *interval;
+                }
+                Some(self.elapsed)
+            }
+            Some(ArrivalProcess::Poisson(profile)) => {
+                self.elapsed += poisson_gap(profile, self.elapsed, &mut self.rng);
+                Some(self.elapsed)
+            }
+        };
+        GeneratedOrder {
+            id,
+            drink,
+            size,
+            amounts,
+            patience,
+            arrival_time,
+        }
+    }
+}
+
+/// Writes the orders described by the configuration to its output, or to the standard output
+/// if there is none.
+pub fn generate(config: &GeneratorConfig) -> Result<(), String> {
+    let output: Box<dyn Write> = match &config.output_path {
+        Some(path) => {
+            Box::new(BufWriter::new(File::create(path).map_err(|error| {
+                format!("No se pudo crear {}: {}", path.display(), error)
+            })?))
+        }
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    let mut generator = Generator::new(config);
+    let orders = (1..=config.count).map(|id| generator.next_order(id));
+    let written = match config.format {
+        InputFormat::Csv => write_csv(config, orders, output),
+        InputFormat::Jsonl => write_jsonl(orders, output),
+    };
+    written.map_err(|error| format!("No se pudieron escribir los pedidos: {}", error))
+}
+
+/// Writes the orders as CSV, with a header with only the columns the configuration fills.
+fn write_csv(
+    config: &GeneratorConfig,
+    orders: impl Iterator<Item = GeneratedOrder>,
+    output: impl Write,
+) -> io::Result<()> {
+    let has_drinks = config
+        .drinks
+        .options()
+        .any(|drink| drink.as_str() != CUSTOM_DRINK);
+    let has_amounts = config
+        .drinks
+        .options()
+        .any(|drink| drink.as_str() == CUSTOM_DRINK);
+    let mut writer = csv::Writer::from_writer(output);
+    let mut header = vec!["id"];
+    if has_drinks {
+        header.push("drink");
+    }
+    if config.sizes.is_some() {
+        header.push("size");
+    }
+    if has_amounts {
+        header.extend(Resource::ALL.map(|ingredient| ingredient.name()));
+    }
+    if config.patience.is_some() {
+        header.push("patience");
+    }
+    if config.arrival_process.is_some() {
+        header.push("arrival_time");
+    }
+    writer.write_record(&header)?;
+
+    for order in orders {
+        let mut record = vec![order.id.to_string()];
+        if has_drinks {
+            record.push(order.drink.unwrap_or_default());
+        }
+        if let Some(size) = order.size {
+            record.push(size.to_string());
+        }
+        if has_amounts {
+            match order.amounts {
+                Some(amounts) => record.extend(amounts.map(|amount| amount.to_string())),
+                None => record.extend(Resource::ALL.map(|_| String::new())),
+            }
+        }
+        if let Some(patience) = order.patience {
+            record.push(patience.to_string());
+        }
+        if let Some(arrival_time) = order.arrival_time {
+            record.push(arrival_time.as_millis().to_string());
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()
+}
+
+/// Writes the orders as JSON lines, with only the fields each order has.
+fn write_jsonl(
+    orders: impl Iterator<Item = GeneratedOrder>,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for order in orders {
+        let mut object = Map::new();
+        object.insert(String::from("id"), Value::from(order.id));
+        if let Some(drink) = order.drink {
+            object.insert(String::from("drink"), Value::from(drink));
+        }
+        if let Some(size) = order.size {
+            object.insert(String::from("size"), Value::from(size.to_string()));
+        }
+        if let Some(amounts) = order.amounts {
+            for ingredient in Resource::ALL {
+                object.insert(
+                    ingredient.name().to_string(),
+                    Value::from(amounts[ingredient as usize]),
+                );
+            }
+        }
+        if let Some(patience) = order.patience {
+            object.insert(String::from("patience"), Value::from(patience));
+        }
+        if let Some(arrival_time) = order.arrival_time {
+            object.insert(
+                String::from("arrival_time"),
+                Value::from(arrival_time.as_millis() as u64),
+            );
+        }
+        writeln!(output, "{}", Value::Object(object))?;
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrival::RateProfile;
+
+    fn rng() -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(7)
+    }
+
+    fn config(format: InputFormat) -> GeneratorConfig {
+        GeneratorConfig {
+            count: 4,
+            output_path: None,
+            format,
+            seed: Some(42),
+            drinks: "latte:1,custom:1".parse().expect("Failed to parse mix"),
+            sizes: Some("small,large".parse().expect("Failed to parse mix")),
+            amounts: [
+                AmountDistribution::Uniform(0, 10),
+                AmountDistribution::Constant(2),
+                AmountDistribution::Poisson(3.0),
+            ],
+            patience: Some(AmountDistribution::Uniform(1000, 5000)),
+            arrival_process: Some(ArrivalProcess::Poisson(
+                "2".parse::<RateProfile>().expect("Failed to parse rate"),
+            )),
+        }
+    }
+
+    fn generated(config: &GeneratorConfig) -> String {
+        let mut generator = Generator::new(config);
+        let orders = (1..=config.count).map(|id| generator.next_order(id));
+        let mut output = Vec::new();
+        match config.format {
+            InputFormat::Csv => write_csv(config, orders, &mut output),
+            InputFormat::Jsonl => write_jsonl(orders, &mut output),
+        }
+        .expect("Failed to write orders");
+        String::from_utf8(output).expect("Failed to read orders")
+    }
+
+    #[test]
+    fn the_same_seed_writes_the_same_csv_orders() {
+        assert_eq!(
+            generated(&config(InputFormat::Csv)),
+            concat!(
+                "id,drink,size,coffee,milk,water,patience,arrival_time\n",
+                "1,,grande,3,2,1,4216,737\n",
+                "2,latte,grande,,,,4608,2310\n",
+                "3,,grande,6,2,2,1618,2503\n",
+                "4,,chico,0,2,6,1810,2627\n",
+            )
+        );
+    }
+
+    #[test]
+    fn the_same_seed_writes_the_same_json_lines() {
+        assert_eq!(
+            generated(&config(InputFormat::Jsonl)),
+            concat!(
+                r#"{"arrival_time":737,"coffee":3,"id":1,"milk":2,"patience":4216,"size":"grande","water":1}"#,
+                "\n",
+                r#"{"arrival_time":2310,"drink":"latte","id":2,"patience":4608,"size":"grande"}"#,
+                "\n",
+                r#"{"arrival_time":2503,"coffee":6,"id":3,"milk":2,"patience":1618,"size":"grande","water":2}"#,
+                "\n",
+                r#"{"arrival_time":2627,"coffee":0,"id":4,"milk":2,"patience":1810,"size":"chico","water":6}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn a_mix_chooses_each_option_as_often_as_its_weight_says() {
+        let mix: Mix<String> = "latte:3,espresso:1,mocha:0"
+            .parse()
+            .expect("Failed to parse mix");
+        let mut rng = rng();
+        let lattes = (0..10_000)
+            .map(|_| mix.choose(&mut rng).as_str())
+            .inspect(|drink| assert_ne!(*drink, "mocha"))
+            .filter(|drink| *drink == "latte")
+            .count();
+
+        assert!((7_200..7_800).contains(&lattes), "{} lattes", lattes);
+    }
+
+    #[test]
+    fn a_mix_needs_an_option_with_positive_weight() {
+        assert!("latte:0".parse::<Mix<String>>().is_err());
+        assert!("latte:-1".parse::<Mix<String>>().is_err());
+        assert!("latte:mucho".parse::<Mix<String>>().is_err());
+        assert_eq!(
+            "latte,espresso:2".parse::<Mix<String>>(),
+            Ok(Mix(vec![
+                (String::from("latte"), 1.0),
+                (String::from("espresso"), 2.0)
+            ]))
+        );
+    }
+
+    #[test]
+    fn amount_distributions_are_parsed_from_an_amount_a_range_or_a_mean() {
+        assert_eq!("2".parse(), Ok(AmountDistribution::Constant(2)));
+        assert_eq!("0-10".parse(), Ok(AmountDistribution::Uniform(0, 10)));
+        assert_eq!("poisson:3.5".parse(), Ok(AmountDistribution::Poisson(3.5)));
+        for invalid in ["", "-1", "10-0", "poisson:-1", "poisson:1000", "mucho"] {
+            assert!(
+                invalid.parse::<AmountDistribution>().is_err(),
+                "{} was accepted",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn amounts_are_drawn_from_their_distribution() {
+        let mut rng = rng();
+        assert_eq!(AmountDistribution::Constant(4).sample(&mut rng), 4);
+        for _ in 0..1_000 {
+            assert!((3..=5).contains(&AmountDistribution::Uniform(3, 5).sample(&mut rng)));
+        }
+        let total: u64 = (0..10_000)
+            .map(|_| AmountDistribution::Poisson(3.0).sample(&mut rng))
+            .sum();
+        let mean = total as f64 / 10_000.0;
+        assert!((2.9..3.1).contains(&mean), "mean {}", mean);
+    }
+}
@@ -1,24 +1,129 @@
+mod alert;
+mod arrival;
+mod benchmark;
 mod blocking_queue;
 mod coffee_machine;
+mod config;
 mod constants;
 mod container;
+mod delivery;
+mod dispatcher;
+mod dispenser;
+mod forecast;
+mod generator;
+mod menu;
 mod order;
+mod order_input;
 mod order_reader;
+mod order_source;
+mod scheduling_policy;
+mod shutdown;
+mod stats;
+mod substitution;
+mod supplier;
 mod utils;
+mod work_stealing;
 
+use crate::benchmark::benchmark_dispatchers;
 use crate::coffee_machine::CoffeeMachine;
-use crate::constants::{
-    BASE_TIME_RESOURCE_REFILL, COFFEE_BEANS_ALERT_THRESHOLD, MILK_FOAM_ALERT_THRESHOLD,
-};
+use crate::config::{Config, GeneratorConfig};
+use crate::constants::{BASE_TIME_RESOURCE_REFILL, GENERATE_COMMAND};
+use crate::generator::generate;
 use crate::order::Order;
-use blocking_queue::BlockingQueue;
-use constants::{
-    BASE_TIME_RESOURCE_APPLICATION, INITIAL_COFFEE_BEANS_TO_GRIND, INITIAL_COLD_MILK,
-    INITIAL_GROUND_COFFEE_BEANS, INITIAL_MILK_FOAM, MAX_DISPENSERS, ORDER_TIME_INTERVAL_ARRIVAL,
-    RESOURCE_ALERT_FACTOR, STATS_UPDATE_INTERVAL,
-};
+use crate::order_input::{OrderInput, SourceLocation, SourceSpec};
+use crate::scheduling_policy::PolicyKind;
+use crate::stats::policy_comparison;
+use constants::{BASE_TIME_RESOURCE_APPLICATION, STATS_UPDATE_INTERVAL};
+use std::io::{Cursor, Read};
+use std::{env, process};
 
 fn main() {
-    let coffee_machine = CoffeeMachine::new();
-    coffee_machine.start();
+    let mut args = env::args().skip(1).peekable();
+    if args.next_if(|arg| arg == GENERATE_COMMAND).is_some() {
+        let generated = GeneratorConfig::from_args(args).and_then(|config| generate(&config));
+        if let Err(error) = generated {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let config = Config::from_args(args).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+
+    if let Some(orders) = config.benchmark_orders {
+        println!("{}", benchmark_dispatchers(&config, orders));
+    } else if config.compare_policies {
+        compare_policies(&config);
+    } else {
+        let coffee_machine = CoffeeMachine::new(&config);
+        shutdown::handle_signals(&coffee_machine).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            process::exit(1);
+        });
+        let outcome = coffee_machine.start(open_inputs(&config));
+        process::exit(outcome.exit_code());
+    }
+}
+
+/// Opens the sources of orders given in the configuration, or the standard input if there is
+/// none
+fn open_inputs(config: &Config) -> Vec<OrderInput> {
+    let stdin = [SourceSpec {
+        name: None,
+        location: SourceLocation::Stdin,
+    }];
+    let sources = match config.sources.as_slice() {
+        [] => &stdin[..],
+        sources => sources,
+    };
+    sources
+        .iter()
+        .map(|source| {
+            source.open(config.input_format).unwrap_or_else(|error| {
+                eprintln!("{}", error);
+                process::exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Runs the same orders once per scheduling policy and prints their latencies side by side
+fn compare_policies(config: &Config) {
+    let inputs: Vec<_> = open_inputs(config)
+        .into_iter()
+        .map(|mut input| {
+            let mut orders = Vec::new();
+            input
+                .stream
+                .read_to_end(&mut orders)
+                .expect("Failed to read orders");
+            (input.name, input.format, orders)
+        })
+        .collect();
+
+    let results: Vec<_> = PolicyKind::ALL
+        .iter()
+        .map(|policy| {
+            let policy_config = Config {
+                policy: *policy,
+                ..config.clone()
+            };
+            let coffee_machine = CoffeeMachine::new(&policy_config);
+            coffee_machine.start(
+                inputs
+                    .iter()
+                    .map(|(name, format, orders)| OrderInput {
+                        name: name.clone(),
+                        format: *format,
+                        stream: Box::new(Cursor::new(orders.clone())),
+                    })
+                    .collect(),
+            );
+            (*policy, coffee_machine.latency_summary())
+        })
+        .collect();
+    println!("{}", policy_comparison(&results));
 }
@@ -0,0 +1,267 @@
+use crate::order::Size;
+use crate::order_reader::OrderParseErrorKind;
+use crate::utils::Resource;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Amount of each ingredient of a drink.
+pub struct Amounts {
+    #[serde(default)]
+    pub coffee: u64,
+    #[serde(default)]
+    pub milk: u64,
+    #[serde(default)]
+    pub water: u64,
+}
+
+impl Amounts {
+    /// Returns the amount of the given ingredient.
+    pub fn get(&self, ingredient: Resource) -> u64 {
+        match ingredient {
+            Resource::Coffee => self.coffee,
+            Resource::Milk => self.milk,
+            Resource::Water => self.water,
+        }
+    }
+
+    /// Returns the amount of the given ingredient, to be changed.
+    fn get_mut(&mut self, ingredient: Resource) -> &mut u64 {
+        match ingredient {
+            Resource::Coffee => &mut self.coffee,
+            Resource::Milk => &mut self.milk,
+            Resource::Water => &mut self.water,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// A step of a recipe, as written in the menu.
+enum Step {
+    /// A single ingredient
+    Single(Resource),
+    /// Ingredients applied at the same time
+    Parallel(Vec<Resource>),
+}
+
+/// Reads the steps of a recipe, each of them an ingredient or a list of ingredients applied
+/// at the same time, like `["milk", ["coffee", "water"]]`.
+fn deserialize_steps<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Vec<Resource>>, D::Error> {
+    let steps = Vec::<Step>::deserialize(deserializer)?;
+    Ok(steps
+        .into_iter()
+        .map(|step| match step {
+            Step::Single(ingredient) => vec![ingredient],
+            Step::Parallel(ingredients) => ingredients,
+        })
+        .collect())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// How a drink is made: the amount of each ingredient, the order in which they are applied
+/// and the extras added at the end.
+pub struct Recipe {
+    #[serde(flatten)]
+    pub amounts: Amounts,
+    /// Order in which the ingredients are applied. The ingredients of the same step are
+    /// applied at the same time, and the ones left out are applied at the end
+    #[serde(
+        default = "Recipe::default_steps",
+        deserialize_with = "deserialize_steps"
+    )]
+    pub steps: Vec<Vec<Resource>>,
+    /// Things added to the drink once the ingredients are applied, like cinnamon or ice
+    #[serde(default)]
+    pub extras: Vec<String>,
+}
+
+impl Recipe {
+    /// Creates a recipe with the given amounts, applied in the usual order.
+    pub fn new(amounts: Amounts) -> Self {
+        Self {
+            amounts,
+            steps: Self::default_steps(),
+            extras: Vec::new(),
+        }
+    }
+
+    /// Usual order of the ingredients: coffee, milk and water, one at a time.
+    pub fn default_steps() -> Vec<Vec<Resource>> {
+        Resource::ALL
+            .iter()
+            .map(|ingredient| vec![*ingredient])
+            .collect()
+    }
+
+    /// Returns the first ingredient that is in more than one step, if any.
+    fn repeated_step(&self) -> Option<Resource> {
+        let ingredients: Vec<Resource> = self.steps.iter().flatten().copied().collect();
+        ingredients
+            .iter()
+            .enumerate()
+            .find(|(index, ingredient)| ingredients[..*index].contains(ingredient))
+            .map(|(_, ingredient)| *ingredient)
+    }
+
+    /// Makes sure every ingredient the drink needs is applied exactly once, leaving out the
+    /// ones it does not need and the steps left empty.
+    fn complete_steps(&mut self) {
+        let mut steps: Vec<Vec<Resource>> = Vec::new();
+        let mut applied = Vec::new();
+        let extra_steps = Self::default_steps();
+        for step in self.steps.iter().chain(extra_steps.iter()) {
+            let step: Vec<Resource> = step
+                .iter()
+                .filter(|ingredient| {
+                    self.amounts.get(**ingredient) > 0 && !applied.contains(*ingredient)
+                })
+                .copied()
+                .collect();
+            applied.extend(step.iter().copied());
+            if !step.is_empty() {
+                steps.push(step);
+            }
+        }
+        self.steps = steps;
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// A change the customer asks for, like "extra shot" or "no milk".
+pub struct Modifier {
+    /// Amounts added to the ones of the drink
+    #[serde(default)]
+    add: Amounts,
+    /// Ingredients left out of the drink
+    #[serde(default)]
+    remove: Vec<Resource>,
+    /// Extras added to the drink
+    #[serde(default)]
+    extras: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Drinks the customers can order by name, and the changes they can ask for.
+pub struct Menu {
+    drinks: BTreeMap<String, Recipe>,
+    #[serde(default)]
+    modifiers: BTreeMap<String, Modifier>,
+}
+
+impl Menu {
+    /// Reads the menu from a JSON file. Names are not case sensitive.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let invalid = |error: String| format!("Menú inválido {}: {}", path.display(), error);
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("No se pudo leer {}: {}", path.display(), error))?;
+        let menu: Menu = serde_json::from_str(&text).map_err(|error| invalid(error.to_string()))?;
+        let mut drinks = BTreeMap::new();
+        for (name, recipe) in menu.drinks {
+            if let Some(ingredient) = recipe.repeated_step() {
+                return Err(invalid(format!(
+                    "{} aplica {} más de una vez",
+                    name,
+                    ingredient.name()
+                )));
+            }
+            if drinks.insert(name.trim().to_lowercase(), recipe).is_some() {
+                return Err(invalid(format!("la bebida {} está repetida", name)));
+            }
+        }
+        let mut modifiers = BTreeMap::new();
+        for (name, modifier) in menu.modifiers {
+            if modifiers
+                .insert(name.trim().to_lowercase(), modifier)
+                .is_some()
+            {
+                return Err(invalid(format!("el modificador {} está repetido", name)));
+            }
+        }
+        Ok(Self { drinks, modifiers })
+    }
+
+    /// Returns the names of the drinks of the menu.
+    pub fn drinks(&self) -> impl Iterator<Item = &String> {
+        self.drinks.keys()
+    }
+
+    /// Returns True if the menu has a drink with the given name.
+    pub fn has_drink(&self, name: &str) -> bool {
+        self.drinks.contains_key(&name.to_lowercase())
+    }
+
+    /// Works out the recipe of an order: the one of the drink in the menu, with the amounts
+    /// given in the order instead of the ones of the menu, scaled to the size, and the
+    /// modifiers applied.
+    /// A drink that is not in the menu can still be ordered if every amount is given.
+    pub fn resolve(
+        &self,
+        drink: Option<&str>,
+        size: Size,
+        amounts: [Option<u64>; 3],
+        modifiers: &[String],
+    ) -> Result<Recipe, OrderParseErrorKind> {
+        let recipe = drink.and_then(|drink| self.drinks.get(&drink.to_lowercase()));
+        let mut recipe = match (recipe, amounts) {
+            (Some(recipe), _) => recipe.clone(),
+            (None, [Some(coffee), Some(milk), Some(water)]) => Recipe::new(Amounts {
+                coffee,
+                milk,
+                water,
+            }),
+            (None, _) => {
+                return Err(match drink {
+                    Some(drink) => OrderParseErrorKind::UnknownDrink(drink.to_string()),
+                    None => {
+                        let missing = Resource::ALL
+                            .iter()
+                            .find(|ingredient| amounts[**ingredient as usize].is_none())
+                            .expect("Failed to find the missing amount");
+                        OrderParseErrorKind::MissingField(missing.field_name())
+                    }
+                })
+            }
+        };
+        for ingredient in Resource::ALL {
+            let amount = recipe.amounts.get_mut(ingredient);
+            if let Some(given) = amounts[ingredient as usize] {
+                *amount = given;
+            }
+            *amount = size.scale(*amount);
+        }
+        for name in modifiers {
+            let modifier = self
+                .modifiers
+                .get(&name.to_lowercase())
+                .ok_or_else(|| OrderParseErrorKind::UnknownModifier(name.clone()))?;
+            for ingredient in Resource::ALL {
+                *recipe.amounts.get_mut(ingredient) += modifier.add.get(ingredient);
+            }
+            for ingredient in &modifier.remove {
+                *recipe.amounts.get_mut(*ingredient) = 0;
+            }
+            recipe.extras.extend(modifier.extras.iter().cloned());
+        }
+        recipe.complete_steps();
+        Ok(recipe)
+    }
+}
+
+/// Splits a list of modifiers separated by `+`, like `extra shot+no milk`.
+pub fn split_modifiers(modifiers: &str) -> Vec<String> {
+    modifiers
+        .split('+')
+        .map(str::trim)
+        .filter(|modifier| !modifier.is_empty())
+        .map(String::from)
+        .collect()
+}
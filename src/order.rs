@@ -1,23 +1,154 @@
+use crate::constants::{LARGE_SIZE_FACTOR, SMALL_SIZE_FACTOR};
+use crate::container::ContainerKind;
+use crate::menu::Recipe;
+use crate::utils::Resource;
 use std::fmt::Display;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Size of a drink, which sets the cup it is served in.
+pub enum Size {
+    Small = 0,
+    #[default]
+    Medium,
+    Large,
+}
+
+impl Size {
+    /// Every size, from the smallest to the largest one.
+    pub const ALL: [Size; 3] = [Size::Small, Size::Medium, Size::Large];
+
+    /// Returns the amount of an ingredient of the recipe of a medium drink, scaled to this
+    /// size. An ingredient is never left out because of the size.
+    pub fn scale(&self, amount: u64) -> u64 {
+        let factor = match self {
+            Size::Small => SMALL_SIZE_FACTOR,
+            Size::Medium => 1.0,
+            Size::Large => LARGE_SIZE_FACTOR,
+        };
+        match amount {
+            0 => 0,
+            amount => ((amount as f64 * factor).round() as u64).max(1),
+        }
+    }
+
+    /// Returns the container of the cups of this size.
+    pub fn cups(&self) -> ContainerKind {
+        match self {
+            Size::Small => ContainerKind::SmallCups,
+            Size::Medium => ContainerKind::MediumCups,
+            Size::Large => ContainerKind::LargeCups,
+        }
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    /// Accepts the names in English or Spanish, or their initial, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "small" | "s" | "chico" | "c" => Ok(Size::Small),
+            "medium" | "m" | "mediano" => Ok(Size::Medium),
+            "large" | "l" | "grande" | "g" => Ok(Size::Large),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Size::Small => "chico",
+            Size::Medium => "mediano",
+            Size::Large => "grande",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+/// Optional information of an order, taken from the extra columns of the input.
+pub struct OrderDetails {
+    pub id: Option<String>,
+    pub customer: Option<String>,
+    pub priority: Option<u64>,
+    /// Time since the start of the day when the customer arrived, according to the input
+    pub arrival_time: Option<Duration>,
+    pub size: Option<String>,
+    pub drink: Option<String>,
+    /// Amounts given in the input instead of the ones of the drink in the menu
+    pub requested_amounts: [Option<u64>; 3],
+    /// Changes to the drink of the menu asked by the customer
+    pub modifiers: Vec<String>,
+    /// Substitutes the customer accepts if an ingredient runs out, or `*` for any of them
+    pub substitutes: Vec<String>,
+    /// Name of the input the order was read from, like the register that took it
+    pub source: Option<String>,
+}
+
+#[derive(Clone)]
 /// Represents a Order for the coffee machine.
 pub struct Order {
     coffee: u64,
     milk: u64,
     water: u64,
+    arrival_time: Instant,
+    patience: Option<Duration>,
+    crashes: u32,
+    /// True once a dispenser took the cup of the order, which it keeps if it is queued again
+    has_cup: bool,
+    size: Size,
+    steps: Vec<Vec<Resource>>,
+    extras: Vec<String>,
+    /// Substitute used instead of each ingredient that ran out, if any
+    substitutions: Vec<(Resource, String)>,
+    details: Box<OrderDetails>,
 }
 
 impl Order {
     /// Creates new order with the given amount of coffee, milk and water.
-    pub fn new(coffee: u64, milk: u64, water: u64) -> Self {
+    /// `patience` is how long the customer is willing to wait for the drink, if limited.
+    pub fn new(coffee: u64, milk: u64, water: u64, patience: Option<Duration>) -> Self {
         Self {
             coffee,
             milk,
             water,
+            arrival_time: Instant::now(),
+            patience,
+            crashes: 0,
+            has_cup: false,
+            size: Size::default(),
+            steps: Recipe::default_steps(),
+            extras: Vec::new(),
+            substitutions: Vec::new(),
+            details: Box::default(),
         }
     }
 
+    /// Creates new order of the given size, prepared as the recipe says.
+    pub fn from_recipe(recipe: Recipe, size: Size, patience: Option<Duration>) -> Self {
+        let amounts = recipe.amounts;
+        Self {
+            size,
+            steps: recipe.steps,
+            extras: recipe.extras,
+            ..Self::new(amounts.coffee, amounts.milk, amounts.water, patience)
+        }
+    }
+
+    /// Attaches the optional information of the order.
+    pub fn with_details(mut self, details: OrderDetails) -> Self {
+        self.details = Box::new(details);
+        self
+    }
+
+    /// Returns the optional information of the order.
+    pub fn get_details(&self) -> &OrderDetails {
+        &self.details
+    }
+
     /// Returns True if the order requires coffee.
     pub fn requires_coffee(&self) -> bool {
         self.coffee > 0
@@ -47,14 +178,132 @@ impl Order {
     pub fn get_milk(&self) -> &u64 {
         &self.milk
     }
+
+    /// Returns the amount of the given ingredient required by the order.
+    pub fn get_amount(&self, ingredient: Resource) -> u64 {
+        match ingredient {
+            Resource::Coffee => self.coffee,
+            Resource::Milk => self.milk,
+            Resource::Water => self.water,
+        }
+    }
+
+    /// Returns the size of the drink.
+    pub fn get_size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the steps of the order, in the order they are applied. The ingredients of the
+    /// same step are applied at the same time.
+    pub fn get_steps(&self) -> &[Vec<Resource>] {
+        &self.steps
+    }
+
+    /// Returns what is added to the drink once the ingredients are applied.
+    pub fn get_extras(&self) -> &[String] {
+        &self.extras
+    }
+
+    /// Records that the ingredient is replaced by the substitute with the given name.
+    pub fn substitute(&mut self, ingredient: Resource, name: String) {
+        self.substitutions.push((ingredient, name));
+    }
+
+    /// Returns the name of the substitute used instead of the ingredient, if it was replaced.
+    pub fn get_substitute(&self, ingredient: Resource) -> Option<&str> {
+        self.substitutions
+            .iter()
+            .find(|(replaced, _)| *replaced == ingredient)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Returns the ingredients replaced by a substitute, along with its name.
+    pub fn get_substitutions(&self) -> &[(Resource, String)] {
+        &self.substitutions
+    }
+
+    /// Returns the total amount of ingredients required by the order.
+    /// Preparation time is proportional to this value.
+    pub fn total_amount(&self) -> u64 {
+        self.coffee
+            .saturating_add(self.milk)
+            .saturating_add(self.water)
+    }
+
+    /// Returns how long the customer waits for the drink, if the order has a limit.
+    pub fn get_patience(&self) -> Option<Duration> {
+        self.patience
+    }
+
+    /// Records that the order reaches the machine now, once the customer arrives.
+    pub fn mark_arrival(&mut self) {
+        self.arrival_time = Instant::now();
+    }
+
+    /// Records that a dispenser crashed while preparing the order.
+    /// Returns how many times that happened.
+    pub fn record_crash(&mut self) -> u32 {
+        self.crashes += 1;
+        self.crashes
+    }
+
+    /// Records that a dispenser took the cup of the order.
+    pub fn mark_cup_taken(&mut self) {
+        self.has_cup = true;
+    }
+
+    /// Returns True if the order already has its cup.
+    pub fn has_cup(&self) -> bool {
+        self.has_cup
+    }
+
+    /// Returns the instant in which the order was taken.
+    pub fn get_arrival_time(&self) -> &Instant {
+        &self.arrival_time
+    }
+
+    /// Returns the instant by which the order is expected to be served, if it has one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.patience.map(|patience| self.arrival_time + patience)
+    }
+
+    /// Returns True if the order has a deadline and it has already passed.
+    pub fn is_expired(&self) -> bool {
+        self.deadline()
+            .is_some_and(|deadline| Instant::now() > deadline)
+    }
 }
 
 impl Display for Order {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pedido")?;
+        if let Some(id) = &self.details.id {
+            write!(f, " #{}", id)?;
+        }
+        if let Some(customer) = &self.details.customer {
+            write!(f, " de {}", customer)?;
+        }
+        if let Some(source) = &self.details.source {
+            write!(f, " desde {}", source)?;
+        }
+        write!(f, " {{ ")?;
+        if let Some(drink) = &self.details.drink {
+            write!(f, "bebida: {}", drink)?;
+            for modifier in &self.details.modifiers {
+                write!(f, " + {}", modifier)?;
+            }
+            write!(f, ", ")?;
+        }
+        if self.details.size.is_some() {
+            write!(f, "tamaño: {}, ", self.size)?;
+        }
+        for (ingredient, name) in &self.substitutions {
+            write!(f, "{} en lugar de {}, ", name, ingredient.field_name())?;
+        }
         write!(
             f,
-            "Pedido {{ cantidad_cafe: {}, cantidad_leche: {}, cantidad_agua: {} }}",
-            self.water, self.coffee, self.milk
+            "cantidad_cafe: {}, cantidad_leche: {}, cantidad_agua: {} }}",
+            self.coffee, self.milk, self.water
         )
     }
 }
@@ -0,0 +1,141 @@
+use crate::constants::{COLOR_BLUE, COLOR_RESET};
+use crate::order_reader::reader_name;
+use crate::order_source::InputFormat;
+use std::fs::File;
+use std::io::{self, Read};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
+/// Where the orders of a source are read from.
+pub enum SourceLocation {
+    Stdin,
+    File(PathBuf),
+    /// Address where the machine waits for a connection, like a register or the mobile app, and
+    /// reads its orders until it closes
+    Tcp(String),
+}
+
+impl FromStr for SourceLocation {
+    type Err = String;
+
+    /// Accepts `-` for the standard input, `tcp:<address>` for a connection, or a path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "" => Err(String::from("Falta la ubicación del origen de pedidos")),
+            "-" => Ok(SourceLocation::Stdin),
+            _ => match s.strip_prefix("tcp:") {
+                Some(address) => Ok(SourceLocation::Tcp(address.to_string())),
+                None => Ok(SourceLocation::File(s.into())),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// A source of orders, like one of the registers of the café.
+pub struct SourceSpec {
+    /// Name the orders of the source are tagged with. If `None`, they are not tagged
+    pub name: Option<String>,
+    pub location: SourceLocation,
+}
+
+impl SourceSpec {
+    /// Opens the source, reading it in the given format, or in the one its extension says
+    /// otherwise. Connections are accepted once the reading starts, so that no source waits
+    /// for another one.
+    pub fn open(&self, format: Option<InputFormat>) -> Result<OrderInput, String> {
+        let (stream, guessed_format): (Box<dyn Read + Send>, _) = match &self.location {
+            SourceLocation::Stdin => (Box::new(io::stdin()), None),
+            SourceLocation::File(path) => {
+                let file = File::open(path)
+                    .map_err(|error| format!("No se pudo leer {}: {}", path.display(), error))?;
+                (Box::new(file), Some(InputFormat::from_path(path)))
+            }
+            SourceLocation::Tcp(address) => {
+                let listener = TcpListener::bind(address).map_err(|error| {
+                    format!("No se pudo escuchar pedidos en {}: {}", address, error)
+                })?;
+                let actor = reader_name(self.name.as_deref());
+                (Box::new(TcpInput::new(listener, actor)), None)
+            }
+        };
+        Ok(OrderInput {
+            name: self.name.clone(),
+            format: format.or(guessed_format).unwrap_or(InputFormat::Csv),
+            stream,
+        })
+    }
+}
+
+impl FromStr for SourceSpec {
+    type Err = String;
+
+    /// Accepts `<name>=<location>`, for example `caja1=pedidos.csv` or `app=tcp:0.0.0.0:7878`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, location) = s.split_once('=').ok_or_else(|| {
+            format!(
+                "Origen de pedidos inválido: {} (se esperaba <nombre>=<ubicación>)",
+                s
+            )
+        })?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(format!("Origen de pedidos sin nombre: {}", s));
+        }
+        Ok(Self {
+            name: Some(name.to_string()),
+            location: location.trim().parse()?,
+        })
+    }
+}
+
+/// An open source of orders.
+pub struct OrderInput {
+    pub name: Option<String>,
+    pub format: InputFormat,
+    pub stream: Box<dyn Read + Send>,
+}
+
+/// Reads the orders of the first connection to the listener, accepting it on the first read.
+struct TcpInput {
+    listener: TcpListener,
+    connection: Option<TcpStream>,
+    /// Name of the reader, used in the messages
+    actor: String,
+}
+
+impl TcpInput {
+    fn new(listener: TcpListener, actor: String) -> Self {
+        Self {
+            listener,
+            connection: None,
+            actor,
+        }
+    }
+}
+
+impl Read for TcpInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let connection = match &mut self.connection {
+            Some(connection) => connection,
+            None => {
+                println!(
+                    "{}[{}]{} - Esperando una conexión en {}",
+                    COLOR_BLUE,
+                    self.actor,
+                    COLOR_RESET,
+                    self.listener.local_addr()?
+                );
+                let (connection, peer) = self.listener.accept()?;
+                println!(
+                    "{}[{}]{} - Leyendo los pedidos de {}",
+                    COLOR_BLUE, self.actor, COLOR_RESET, peer
+                );
+                self.connection.insert(connection)
+            }
+        };
+        connection.read(buf)
+    }
+}
@@ -1,53 +1,430 @@
-use crate::constants::{COLOR_BLUE, COLOR_RESET};
-use crate::utils::{Message, Resource};
-use crate::{BlockingQueue, Order, ORDER_TIME_INTERVAL_ARRIVAL};
-use std::io::Read;
-use std::sync::Arc;
-use std::thread;
+use crate::arrival::{ArrivalClock, ArrivalProcess};
+use crate::config::Config;
+use crate::constants::{COLOR_BLUE, COLOR_RED, COLOR_RESET};
+use crate::dispatcher::Dispatcher;
+use crate::menu::Menu;
+use crate::order_input::OrderInput;
+use crate::order_source::{OrderSource, Row};
+use crate::stats::{RejectStats, SourceStats};
+use crate::utils::Message;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-/// Reads orders from input_stream and pushes them to the output_queue.
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// What the reader does with the rows that are not valid orders.
+pub enum InvalidOrderPolicy {
+    /// The row is discarded and the reading goes on
+    Skip,
+    /// The machine stops, as it does when any of its threads fails
+    Stop,
+    /// The row is written to the rejects file, along with the reason, and the reading goes on
+    Quarantine,
+}
+
+impl FromStr for InvalidOrderPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(InvalidOrderPolicy::Skip),
+            "stop" => Ok(InvalidOrderPolicy::Stop),
+            "quarantine" => Ok(InvalidOrderPolicy::Quarantine),
+            _ => Err(format!(
+                "Política de pedidos inválidos desconocida: {} (opciones: skip, stop, quarantine)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for InvalidOrderPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            InvalidOrderPolicy::Skip => "skip",
+            InvalidOrderPolicy::Stop => "stop",
+            InvalidOrderPolicy::Quarantine => "quarantine",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug)]
+/// Why a row could not be read as an order.
+pub enum OrderParseErrorKind {
+    /// The row is not valid in the format of the input, or not UTF-8
+    Unreadable(String),
+    /// The row describes the input, but not in a way the orders can be read
+    InvalidHeader(String),
+    /// The row does not have the field
+    MissingField(&'static str),
+    /// The drink is not in the menu, and the row does not give every amount
+    UnknownDrink(String),
+    /// The change asked for the drink is not in the menu
+    UnknownModifier(String),
+    /// The size of the drink is not small, medium or large
+    UnknownSize(String),
+    /// The arrival time is not a number of milliseconds, a time of the day or a `+` offset
+    InvalidArrivalTime(String),
+    /// The value of the field is a negative number
+    Negative { field: &'static str, value: String },
+    /// The value of the field is not a number
+    NotANumber { field: &'static str, value: String },
+}
+
+impl Display for OrderParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderParseErrorKind::Unreadable(error) => write!(f, "fila ilegible: {}", error),
+            OrderParseErrorKind::InvalidHeader(error) => write!(f, "{}", error),
+            OrderParseErrorKind::MissingField(field) => write!(f, "falta el campo de {}", field),
+            OrderParseErrorKind::UnknownDrink(drink) => {
+                write!(f, "la bebida {} no está en el menú", drink)
+            }
+            OrderParseErrorKind::UnknownModifier(modifier) => {
+                write!(f, "el modificador {} no está en el menú", modifier)
+            }
+            OrderParseErrorKind::UnknownSize(size) => {
+                write!(
+                    f,
+                    "tamaño desconocido: {} (opciones: chico, mediano, grande)",
+                    size
+                )
+            }
+            OrderParseErrorKind::InvalidArrivalTime(value) => write!(
+                f,
+                "hora de llegada inválida: {} (se esperaban milisegundos, HH:MM[:SS] o +milisegundos)",
+                value
+            ),
+            OrderParseErrorKind::Negative { field, value } => {
+                write!(f, "valor de {} negativo: {}", field, value)
+            }
+            OrderParseErrorKind::NotANumber { field, value } => {
+                write!(f, "valor de {} no numérico: {}", field, value)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A row of the input that could not be read as an order.
+pub struct OrderParseError {
+    line: u64,
+    row: String,
+    kind: OrderParseErrorKind,
+}
+
+impl OrderParseError {
+    /// Creates the error of the row that starts at the given line.
+    pub fn new(line: u64, row: String, kind: OrderParseErrorKind) -> Self {
+        Self { line, row, kind }
+    }
+
+    /// Returns the line of the input where the row starts.
+    pub fn get_line(&self) -> u64 {
+        self.line
+    }
+
+    /// Returns the row, as it was in the input.
+    pub fn get_row(&self) -> &str {
+        &self.row
+    }
+
+    /// Returns why the row is not a valid order.
+    pub fn get_kind(&self) -> &OrderParseErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for OrderParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self.kind {
+            OrderParseErrorKind::InvalidHeader(_) => "Encabezado inválido",
+            _ => "Pedido inválido",
+        };
+        write!(f, "{} en la línea {}: {}", what, self.line, self.kind)
+    }
+}
+
+/// Reads orders from the inputs and pushes them to the output_queue. Each input is read by its
+/// own thread, all of them sharing the reader.
 pub struct OrderReader {
-    output_queue: Arc<BlockingQueue<Message>>,
+    output_queue: Arc<dyn Dispatcher>,
+    default_patience: Option<Duration>,
+    stop: Arc<AtomicBool>,
+    on_invalid: InvalidOrderPolicy,
+    rejects_path: PathBuf,
+    /// Rejects file, created once there is a row to quarantine
+    rejects: Mutex<Option<File>>,
+    menu: Arc<Menu>,
+    delimiter: u8,
+    comment: Option<u8>,
+    arrival_process: ArrivalProcess,
+    arrival_speed: f64,
+    arrival_seed: Option<u64>,
+    reject_stats: Mutex<RejectStats>,
+    source_stats: Arc<Mutex<SourceStats>>,
 }
 
 impl OrderReader {
-    /// Create new order reader
-    pub fn new(output_stream: Arc<BlockingQueue<Message>>) -> Self {
+    /// Create new order reader, with the handling of invalid rows given in the configuration.
+    /// The orders of the named inputs are counted in `source_stats`.
+    /// Once `stop` is set, no more orders are taken.
+    pub fn new(
+        output_stream: Arc<dyn Dispatcher>,
+        stop: Arc<AtomicBool>,
+        source_stats: Arc<Mutex<SourceStats>>,
+        config: &Config,
+    ) -> Self {
         Self {
             output_queue: output_stream,
+            default_patience: config.default_patience,
+            stop,
+            on_invalid: config.on_invalid_order,
+            rejects_path: config.rejects_path.clone(),
+            rejects: Mutex::new(None),
+            menu: config.menu.clone(),
+            delimiter: config.delimiter,
+            comment: config.comment,
+            arrival_process: config.arrival_process.clone(),
+            arrival_speed: config.arrival_speed,
+            arrival_seed: config.arrival_seed,
+            reject_stats: Mutex::new(RejectStats::new()),
+            source_stats,
         }
     }
 
-    /// Starts reading orders from input_stream and pushing them to the output_queue.
-    pub fn read_from(&self, input_stream: impl Read) {
-        let mut reader = csv::ReaderBuilder::new()
-            .has_headers(false)
-            .from_reader(input_stream);
-        for result in reader.records() {
-            println!(
-                "{}[Lector de pedidos]{} - Tomando pedido",
-                COLOR_BLUE, COLOR_RESET
-            );
-            let record = result.expect("Failed to read record");
-            let order = Order::new(
-                record[Resource::Coffee as usize]
-                    .parse()
-                    .expect("Failed to parse coffee"),
-                record[Resource::Milk as usize]
-                    .parse()
-                    .expect("Failed to parse milk"),
-                record[Resource::Water as usize]
-                    .parse()
-                    .expect("Failed to parse water"),
-            );
+    /// Starts reading orders from the input and pushing them to the output_queue, tagged with
+    /// the name of the input if it has one.
+    /// The rows are read by the source of the input format. Empty lines and comments are
+    /// skipped.
+    /// Each order is pushed once it arrives, at its arrival time if it has one, or as the
+    /// arrival process says otherwise. `position` is the one of the input among all of them,
+    /// so that each input draws different Poisson arrivals from the same seed.
+    /// Returns an error if the input could not be read, if the header or an invalid row stopped
+    /// the reading, or if a row could not be quarantined.
+    pub fn read_from(&self, input: OrderInput, position: u64) -> Result<(), String> {
+        let actor = reader_name(input.name.as_deref());
+        let mut source = input.format.source(
+            input.stream,
+            self.delimiter,
+            self.comment,
+            self.default_patience,
+            self.menu.clone(),
+            input.name.clone(),
+        );
+        let mut clock = ArrivalClock::new(
+            self.arrival_process.clone(),
+            self.arrival_speed,
+            self.arrival_seed.map(|seed| seed.wrapping_add(position)),
+        );
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                break;
+            }
+            // Si falla la lectura, no hay más filas que se puedan leer.
+            let row = source
+                .next_row()
+                .map_err(|error| format!("No se pudieron leer los pedidos: {}", error))?;
+            let mut order = match row {
+                None => break,
+                Some(Row::Skipped) => continue,
+                Some(Row::Order(order)) => {
+                    println!("{}[{}]{} - Tomando pedido", COLOR_BLUE, actor, COLOR_RESET);
+                    order
+                }
+                Some(Row::Invalid(error)) => {
+                    println!("{}[{}]{} - Tomando pedido", COLOR_BLUE, actor, COLOR_RESET);
+                    self.reject(error, source.as_ref(), input.name.as_deref())?;
+                    continue;
+                }
+            };
+            // Se espera a que llegue el cliente, para simular que todos los pedidos no llegan de inmediato.
+            if !clock.wait_for(&order, &self.stop) {
+                break;
+            }
+            order.mark_arrival();
             println!(
-                "{}[Lector de pedidos]{} - Pedido tomado y anotado: {}",
-                COLOR_BLUE, COLOR_RESET, order
+                "{}[{}]{} - Pedido tomado y anotado: {}",
+                COLOR_BLUE, actor, COLOR_RESET, order
             );
-            self.output_queue.push_back(Message::Job(order));
-            // Sleep para simular que todos los pedidos no llegan de inmediato. Similar a clientes.
-            thread::sleep(Duration::from_millis(ORDER_TIME_INTERVAL_ARRIVAL));
+            if let Some(name) = &input.name {
+                self.source_stats
+                    .lock()
+                    .expect("Failed to lock source_stats")
+                    .record_taken(name);
+            }
+            self.output_queue.push(Message::Job(order));
         }
+        Ok(())
+    }
+
+    /// Handles a row that is not a valid order, as the policy says. An invalid header always
+    /// stops the reading, since no row after it could be read.
+    /// The row is counted under the input it came from, if it has a name.
+    /// Returns an error if the reading has to stop.
+    fn reject(
+        &self,
+        error: OrderParseError,
+        source: &dyn OrderSource,
+        input_name: Option<&str>,
+    ) -> Result<(), String> {
+        if let OrderParseErrorKind::InvalidHeader(_) = error.get_kind() {
+            return Err(error.to_string());
+        }
+        self.reject_stats
+            .lock()
+            .expect("Failed to lock reject_stats")
+            .record(self.on_invalid);
+        if let Some(name) = input_name {
+            self.source_stats
+                .lock()
+                .expect("Failed to lock source_stats")
+                .record_rejected(name);
+        }
+        let actor = reader_name(input_name);
+        match self.on_invalid {
+            InvalidOrderPolicy::Stop => return Err(error.to_string()),
+            InvalidOrderPolicy::Skip => println!(
+                "{}[{}]{} - Descartando: {}",
+                COLOR_RED, actor, COLOR_RESET, error
+            ),
+            InvalidOrderPolicy::Quarantine => {
+                println!(
+                    "{}[{}]{} - En cuarentena: {}",
+                    COLOR_RED, actor, COLOR_RESET, error
+                );
+                self.write_reject(source, input_name, &error)
+                    .map_err(|io_error| rejects_error(&self.rejects_path, io_error.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a rejected row to the rejects file, preceded by a comment line with the reason
+    /// and the input it came from, so the file can be fixed and read again. The file is only
+    /// created once there is a row to write, starting with the header of the input if it had
+    /// one.
+    fn write_reject(
+        &self,
+        source: &dyn OrderSource,
+        input_name: Option<&str>,
+        error: &OrderParseError,
+    ) -> std::io::Result<()> {
+        let mut rejects = self.rejects.lock().expect("Failed to lock rejects");
+        let file = match &mut *rejects {
+            Some(file) => file,
+            None => {
+                let mut file = File::create(&self.rejects_path)?;
+                if let Some(header) = source.header() {
+                    writeln!(file, "{}", header)?;
+                }
+                rejects.insert(file)
+            }
+        };
+        write!(file, "{} ", self.comment.unwrap_or(b'#') as char)?;
+        if let Some(name) = input_name {
+            write!(file, "{} ", name)?;
+        }
+        writeln!(file, "línea {}: {}", error.get_line(), error.get_kind())?;
+        writeln!(file, "{}", error.get_row())
+    }
+
+    /// Returns how many rows were rejected, and what was done with them.
+    pub fn get_reject_stats(&self) -> RejectStats {
+        *self
+            .reject_stats
+            .lock()
+            .expect("Failed to lock reject_stats")
+    }
+
+    /// Returns the file where the quarantined rows are written.
+    pub fn get_rejects_path(&self) -> &Path {
+        &self.rejects_path
+    }
+}
+
+/// Returns the name of the reader of the input with the given name, used in the messages.
+pub fn reader_name(input_name: Option<&str>) -> String {
+    match input_name {
+        Some(name) => format!("Lector de pedidos {}", name),
+        None => String::from("Lector de pedidos"),
+    }
+}
+
+/// Describes an error writing the rejects file.
+fn rejects_error(path: &Path, error: String) -> String {
+    format!(
+        "No se pudo escribir el archivo de rechazos {}: {}",
+        path.display(),
+        error
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_source::InputFormat;
+    use std::io::{self, Cursor, Read};
+
+    /// Input whose reads always fail, like a directory given as the orders file.
+    struct FailingInput;
+
+    impl Read for FailingInput {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("Is a directory"))
+        }
+    }
+
+    fn reader(on_invalid_order: InvalidOrderPolicy) -> (OrderReader, Arc<dyn Dispatcher>) {
+        let config = Config {
+            on_invalid_order,
+            arrival_process: ArrivalProcess::Fixed(Duration::ZERO),
+            ..Config::default()
+        };
+        let dispatcher = config.dispatcher.build();
+        let reader = OrderReader::new(
+            dispatcher.clone(),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(Mutex::new(SourceStats::new())),
+            &config,
+        );
+        (reader, dispatcher)
+    }
+
+    fn input(stream: impl Read + Send + 'static) -> OrderInput {
+        OrderInput {
+            name: None,
+            format: InputFormat::Csv,
+            stream: Box::new(stream),
+        }
+    }
+
+    #[test]
+    fn a_failing_input_stops_the_reading_instead_of_rejecting_rows() {
+        for policy in [InvalidOrderPolicy::Skip, InvalidOrderPolicy::Quarantine] {
+            let (reader, _) = reader(policy);
+            let error = reader
+                .read_from(input(FailingInput), 0)
+                .expect_err("Expected the reading to fail");
+            assert!(error.contains("Is a directory"));
+            assert_eq!(reader.get_reject_stats().total(), 0);
+        }
+    }
+
+    #[test]
+    fn a_row_that_is_not_utf8_is_rejected_and_the_reading_goes_on() {
+        let (reader, dispatcher) = reader(InvalidOrderPolicy::Skip);
+        let rows = b"1,2,3\n\xff,1,1\n4,5,6\n".to_vec();
+        reader
+            .read_from(input(Cursor::new(rows)), 0)
+            .expect("Failed to read orders");
+        assert_eq!(reader.get_reject_stats().total(), 1);
+        assert_eq!(dispatcher.drain().len(), 2);
     }
 }
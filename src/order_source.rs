@@ -0,0 +1,788 @@
+use crate::constants::{COLOR_BLUE, COLOR_RESET};
+use crate::menu::{split_modifiers, Menu};
+use crate::order::{OrderDetails, Size};
+use crate::order_reader::{reader_name, OrderParseError, OrderParseErrorKind};
+use crate::utils::Resource;
+use crate::Order;
+use serde_json::{Map, Value};
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Position of the optional column with the patience (in milliseconds) of the customer
+const PATIENCE_COLUMN: usize = 3;
+
+/// What a source found in the next row of its input.
+pub enum Row {
+    Order(Order),
+    /// The row is not an order, like a CSV header, a comment or an empty line
+    Skipped,
+    /// The row could not be read as an order
+    Invalid(OrderParseError),
+}
+
+/// Input the orders are read from, in one of the formats.
+pub trait OrderSource {
+    /// Reads and parses the next row of the input.
+    /// Returns `None` once there are no more rows, and an error if the input could not be read.
+    fn next_row(&mut self) -> io::Result<Option<Row>>;
+
+    /// Returns the row that described the input, if there was one, so the rejects file can
+    /// start with it.
+    fn header(&self) -> Option<&str>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Formats the orders can be read in.
+pub enum InputFormat {
+    /// One order per row, with its fields separated by the delimiter
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl InputFormat {
+    /// Guesses the format from the extension of the file: `.jsonl`, `.ndjson` and `.json`
+    /// files are JSON lines, anything else is CSV.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("jsonl" | "ndjson" | "json") => InputFormat::Jsonl,
+            _ => InputFormat::Csv,
+        }
+    }
+
+    /// Creates the source that reads the rows of this format from the stream, taking the
+    /// drinks ordered by name from the menu and tagging the orders with the name of the input,
+    /// if it has one. Rows that start with the comment character are skipped.
+    pub fn source(
+        &self,
+        stream: Box<dyn Read + Send>,
+        delimiter: u8,
+        comment: Option<u8>,
+        default_patience: Option<Duration>,
+        menu: Arc<Menu>,
+        input_name: Option<String>,
+    ) -> Box<dyn OrderSource> {
+        let builder = OrderBuilder {
+            menu,
+            default_patience,
+            last_arrival: Duration::ZERO,
+            input_name,
+        };
+        match self {
+            InputFormat::Csv => Box::new(CsvSource::new(stream, delimiter, comment, builder)),
+            InputFormat::Jsonl => Box::new(JsonlSource::new(stream, comment, builder)),
+        }
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(InputFormat::Csv),
+            "jsonl" => Ok(InputFormat::Jsonl),
+            _ => Err(format!(
+                "Formato de pedidos desconocido: {} (opciones: csv, jsonl)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for InputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            InputFormat::Csv => "csv",
+            InputFormat::Jsonl => "jsonl",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Arrival time of an order, as given in the input.
+enum ArrivalTime {
+    /// Time since the start of the day
+    At(Duration),
+    /// Time since the arrival of the previous order
+    After(Duration),
+}
+
+impl FromStr for ArrivalTime {
+    type Err = OrderParseErrorKind;
+
+    /// Accepts milliseconds since the start of the day (`28800000`), a time of the day
+    /// (`08:00`, `08:00:30` or `08:00:30.250`) or milliseconds after the previous order (`+500`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OrderParseErrorKind::InvalidArrivalTime(s.to_string());
+        if let Some(offset) = s.strip_prefix('+') {
+            let offset = offset.trim().parse().map_err(|_| invalid())?;
+            return Ok(ArrivalTime::After(Duration::from_millis(offset)));
+        }
+        let parts: Vec<&str> = s.split(':').collect();
+        if let [millis] = parts[..] {
+            let millis = millis.parse().map_err(|_| invalid())?;
+            return Ok(ArrivalTime::At(Duration::from_millis(millis)));
+        }
+        let (hours, minutes, seconds) = match parts[..] {
+            [hours, minutes] => (hours, minutes, "0"),
+            [hours, minutes, seconds] => (hours, minutes, seconds),
+            _ => return Err(invalid()),
+        };
+        let hours: u64 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u64 = minutes.parse().map_err(|_| invalid())?;
+        let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 || !(0.0..60.0).contains(&seconds) {
+            return Err(invalid());
+        }
+        Ok(ArrivalTime::At(
+            Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds),
+        ))
+    }
+}
+
+/// Turns the fields read from a row into an order, working out its recipe from the menu.
+struct OrderBuilder {
+    menu: Arc<Menu>,
+    default_patience: Option<Duration>,
+    /// Arrival time of the last order that had one, from which the `+` offsets are counted
+    last_arrival: Duration,
+    /// Name of the input the orders are tagged with
+    input_name: Option<String>,
+}
+
+impl OrderBuilder {
+    /// Builds the order of the drink in the details, in the size it asks for, with the
+    /// amounts given in the row instead of the ones of the menu. The arrival time of the
+    /// details is the one given in the row, counted from the start of the day.
+    fn build(
+        &mut self,
+        amounts: [Option<u64>; 3],
+        patience: Option<u64>,
+        arrival_time: Option<&str>,
+        mut details: OrderDetails,
+    ) -> Result<Order, OrderParseErrorKind> {
+        details.arrival_time = match arrival_time.map(str::parse).transpose()? {
+            Some(ArrivalTime::At(at)) => Some(at),
+            Some(ArrivalTime::After(offset)) => Some(self.last_arrival + offset),
+            None => None,
+        };
+        let size = match &details.size {
+            Some(size) => size.parse().map_err(OrderParseErrorKind::UnknownSize)?,
+            None => Size::default(),
+        };
+        let recipe =
+            self.menu
+                .resolve(details.drink.as_deref(), size, amounts, &details.modifiers)?;
+        let patience = patience
+            .map(Duration::from_millis)
+            .or(self.default_patience);
+        details.requested_amounts = amounts;
+        if let Some(arrival_time) = details.arrival_time {
+            self.last_arrival = arrival_time;
+        }
+        details.source = self.input_name.clone();
+        Ok(Order::from_recipe(recipe, size, patience).with_details(details))
+    }
+}
+
+/// Columns of the input where each field of the orders is.
+#[derive(Clone, Copy, Debug, Default)]
+struct ColumnMap {
+    coffee: Option<usize>,
+    milk: Option<usize>,
+    water: Option<usize>,
+    patience: Option<usize>,
+    id: Option<usize>,
+    customer: Option<usize>,
+    priority: Option<usize>,
+    arrival_time: Option<usize>,
+    size: Option<usize>,
+    drink: Option<usize>,
+    modifiers: Option<usize>,
+    substitutes: Option<usize>,
+}
+
+impl ColumnMap {
+    /// Columns of an input without header: coffee, milk, water and the optional patience, in
+    /// that order.
+    fn positional() -> Self {
+        Self {
+            coffee: Some(Resource::Coffee as usize),
+            milk: Some(Resource::Milk as usize),
+            water: Some(Resource::Water as usize),
+            patience: Some(PATIENCE_COLUMN),
+            ..Default::default()
+        }
+    }
+
+    /// Maps the columns by the names of the header, in any order and ignoring case. Unknown
+    /// columns are ignored.
+    /// Returns `None` if the row has no known column name, so it is not a header, and an error
+    /// if a column is repeated or the amount of an ingredient is missing without a drink column
+    /// to take it from the menu.
+    fn from_header(header: &csv::StringRecord) -> Option<Result<Self, String>> {
+        let mut columns = Self::default();
+        let mut is_header = false;
+        for (index, name) in header.iter().enumerate() {
+            let name = name.trim().to_lowercase();
+            let column = match name.as_str() {
+                "coffee" => &mut columns.coffee,
+                "milk" => &mut columns.milk,
+                "water" => &mut columns.water,
+                "patience" => &mut columns.patience,
+                "id" => &mut columns.id,
+                "customer" => &mut columns.customer,
+                "priority" => &mut columns.priority,
+                "arrival_time" => &mut columns.arrival_time,
+                "size" => &mut columns.size,
+                "drink" => &mut columns.drink,
+                "modifiers" => &mut columns.modifiers,
+                "substitutes" => &mut columns.substitutes,
+                _ => continue,
+            };
+            is_header = true;
+            if column.is_some() {
+                return Some(Err(format!("la columna {} está repetida", name)));
+            }
+            *column = Some(index);
+        }
+        if !is_header {
+            return None;
+        }
+        if columns.drink.is_some() {
+            return Some(Ok(columns));
+        }
+        for (column, name) in [
+            (columns.coffee, "coffee"),
+            (columns.milk, "milk"),
+            (columns.water, "water"),
+        ] {
+            if column.is_none() {
+                return Some(Err(format!("falta la columna {}", name)));
+            }
+        }
+        Some(Ok(columns))
+    }
+}
+
+/// Reads orders from CSV rows. If the first row is a header, the columns are mapped by name.
+/// Otherwise they are taken in positional order.
+/// The rows are streamed by a single CSV reader, so a quoted field can span several lines.
+pub struct CsvSource {
+    reader: csv::Reader<Box<dyn Read + Send>>,
+    delimiter: u8,
+    builder: OrderBuilder,
+    columns: Option<ColumnMap>,
+    header: Option<String>,
+}
+
+impl CsvSource {
+    /// Creates a source of the rows of the stream, with their fields separated by the given
+    /// delimiter.
+    fn new(
+        stream: Box<dyn Read + Send>,
+        delimiter: u8,
+        comment: Option<u8>,
+        builder: OrderBuilder,
+    ) -> Self {
+        Self {
+            reader: csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .delimiter(delimiter)
+                .comment(comment)
+                .from_reader(stream),
+            delimiter,
+            builder,
+            columns: None,
+            header: None,
+        }
+    }
+
+    /// Writes the fields back as a row, quoted as needed, to show it in the errors and in the
+    /// rejects file.
+    fn row_text(&self, record: &csv::ByteRecord) -> String {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(Vec::new());
+        writer
+            .write_byte_record(record)
+            .expect("Failed to write row");
+        let row = writer.into_inner().expect("Failed to write row");
+        String::from_utf8_lossy(&row).trim_end().to_string()
+    }
+
+    /// Parses a row, found at the given line.
+    /// Returns `None` if the row is not an order but the header of the input.
+    fn parse(
+        &mut self,
+        record: &csv::StringRecord,
+        row: &str,
+        line: u64,
+    ) -> Result<Option<Order>, OrderParseError> {
+        let invalid = |kind| OrderParseError::new(line, row.to_string(), kind);
+        let columns = match self.columns {
+            Some(columns) => columns,
+            None => match ColumnMap::from_header(record) {
+                Some(header) => {
+                    let columns = header
+                        .map_err(|error| invalid(OrderParseErrorKind::InvalidHeader(error)))?;
+                    println!(
+                        "{}[{}]{} - Leyendo las columnas por nombre según el encabezado",
+                        COLOR_BLUE,
+                        reader_name(self.builder.input_name.as_deref()),
+                        COLOR_RESET
+                    );
+                    self.columns = Some(columns);
+                    self.header = Some(row.to_string());
+                    return Ok(None);
+                }
+                None => *self.columns.insert(ColumnMap::positional()),
+            },
+        };
+
+        let text = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(String::from)
+        };
+        let optional_number = |column: Option<usize>, field| match (column, text(column)) {
+            (Some(column), Some(_)) => parse_field(record, column, field).map(Some),
+            _ => Ok(None),
+        };
+
+        let amounts = [
+            optional_number(columns.coffee, "café").map_err(invalid)?,
+            optional_number(columns.milk, "leche").map_err(invalid)?,
+            optional_number(columns.water, "agua").map_err(invalid)?,
+        ];
+        let patience = optional_number(columns.patience, "paciencia").map_err(invalid)?;
+        let details = OrderDetails {
+            id: text(columns.id),
+            customer: text(columns.customer),
+            priority: optional_number(columns.priority, "prioridad").map_err(invalid)?,
+            arrival_time: None,
+            size: text(columns.size),
+            drink: text(columns.drink),
+            requested_amounts: [None; 3],
+            modifiers: text(columns.modifiers)
+                .map(|modifiers| split_modifiers(&modifiers))
+                .unwrap_or_default(),
+            substitutes: text(columns.substitutes)
+                .map(|substitutes| split_modifiers(&substitutes))
+                .unwrap_or_default(),
+            source: None,
+        };
+        self.builder
+            .build(
+                amounts,
+                patience,
+                text(columns.arrival_time).as_deref(),
+                details,
+            )
+            .map(Some)
+            .map_err(invalid)
+    }
+}
+
+impl OrderSource for CsvSource {
+    fn next_row(&mut self) -> io::Result<Option<Row>> {
+        let mut record = csv::ByteRecord::new();
+        match self.reader.read_byte_record(&mut record) {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(error) if error.is_io_error() => return Err(error.into()),
+            Err(error) => {
+                return Ok(Some(Row::Invalid(OrderParseError::new(
+                    self.reader.position().line(),
+                    String::new(),
+                    OrderParseErrorKind::Unreadable(error.to_string()),
+                ))))
+            }
+        }
+        // El lector de CSV ya saltea las líneas vacías, pero no las que solo tienen espacios.
+        if record.len() == 1 && record[0].trim_ascii().is_empty() {
+            return Ok(Some(Row::Skipped));
+        }
+        let line = record.position().map_or(0, |position| position.line());
+        let row = self.row_text(&record);
+        let record = match csv::StringRecord::from_byte_record(record) {
+            Ok(record) => record,
+            Err(error) => {
+                return Ok(Some(Row::Invalid(OrderParseError::new(
+                    line,
+                    row,
+                    OrderParseErrorKind::Unreadable(error.to_string()),
+                ))))
+            }
+        };
+        Ok(Some(match self.parse(&record, &row, line) {
+            Ok(Some(order)) => Row::Order(order),
+            Ok(None) => Row::Skipped,
+            Err(error) => Row::Invalid(error),
+        }))
+    }
+
+    fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+}
+
+/// Parses the number in the given column of the record, named `field` in the errors.
+fn parse_field(
+    record: &csv::StringRecord,
+    column: usize,
+    field: &'static str,
+) -> Result<u64, OrderParseErrorKind> {
+    let value = record
+        .get(column)
+        .ok_or(OrderParseErrorKind::MissingField(field))?
+        .trim();
+    value.parse().map_err(|_| {
+        let value = value.to_string();
+        if value.parse::<i64>().is_ok_and(|number| number < 0) {
+            OrderParseErrorKind::Negative { field, value }
+        } else {
+            OrderParseErrorKind::NotANumber { field, value }
+        }
+    })
+}
+
+/// Reads orders from lines with a JSON object each, with the same fields as the CSV columns.
+/// The amounts of a nested `recipe` object, if any, take precedence over the ones at the top.
+/// The modifiers and substitutes can be a list or a text separated by `+`.
+pub struct JsonlSource {
+    input: BufReader<Box<dyn Read + Send>>,
+    comment: Option<u8>,
+    builder: OrderBuilder,
+    /// Line of the last row read
+    line: u64,
+}
+
+impl JsonlSource {
+    /// Creates a source of the JSON lines of the stream.
+    fn new(stream: Box<dyn Read + Send>, comment: Option<u8>, builder: OrderBuilder) -> Self {
+        Self {
+            input: BufReader::new(stream),
+            comment,
+            builder,
+            line: 0,
+        }
+    }
+
+    /// Parses a row, found at the given line.
+    fn parse(&mut self, row: &str, line: u64) -> Result<Order, OrderParseError> {
+        let invalid = |kind| OrderParseError::new(line, row.to_string(), kind);
+        let object = match serde_json::from_str(row) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => {
+                return Err(invalid(OrderParseErrorKind::Unreadable(String::from(
+                    "no es un objeto JSON",
+                ))))
+            }
+            Err(error) => return Err(invalid(OrderParseErrorKind::Unreadable(error.to_string()))),
+        };
+        let recipe = match object.get("recipe") {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(recipe)) => Some(recipe),
+            Some(_) => {
+                return Err(invalid(OrderParseErrorKind::Unreadable(String::from(
+                    "recipe no es un objeto JSON",
+                ))))
+            }
+        };
+        let amount = |ingredient: Resource| {
+            recipe
+                .and_then(|recipe| json_field(recipe, ingredient.name()))
+                .or_else(|| json_field(&object, ingredient.name()))
+                .map(|value| json_number(value, ingredient.field_name()))
+                .transpose()
+        };
+        let optional_number = |name, field| {
+            json_field(&object, name)
+                .map(|value| json_number(value, field))
+                .transpose()
+        };
+        let list = |name| {
+            let not_a_list =
+                || OrderParseErrorKind::Unreadable(format!("{} no es una lista de textos", name));
+            match json_field(&object, name) {
+                None => Ok(Vec::new()),
+                Some(Value::String(list)) => Ok(split_modifiers(list)),
+                Some(Value::Array(list)) => list
+                    .iter()
+                    .map(|item| item.as_str().map(String::from))
+                    .collect::<Option<_>>()
+                    .ok_or_else(not_a_list),
+                Some(_) => Err(not_a_list()),
+            }
+        };
+
+        let amounts = [
+            amount(Resource::Coffee).map_err(invalid)?,
+            amount(Resource::Milk).map_err(invalid)?,
+            amount(Resource::Water).map_err(invalid)?,
+        ];
+        let patience = optional_number("patience", "paciencia").map_err(invalid)?;
+        let details = OrderDetails {
+            id: json_text(&object, "id"),
+            customer: json_text(&object, "customer"),
+            priority: optional_number("priority", "prioridad").map_err(invalid)?,
+            arrival_time: None,
+            size: json_text(&object, "size"),
+            drink: json_text(&object, "drink"),
+            requested_amounts: [None; 3],
+            modifiers: list("modifiers").map_err(invalid)?,
+            substitutes: list("substitutes").map_err(invalid)?,
+            source: None,
+        };
+        self.builder
+            .build(
+                amounts,
+                patience,
+                json_text(&object, "arrival_time").as_deref(),
+                details,
+            )
+            .map_err(invalid)
+    }
+}
+
+impl OrderSource for JsonlSource {
+    fn next_row(&mut self) -> io::Result<Option<Row>> {
+        let mut buffer = Vec::new();
+        if self.input.read_until(b'\n', &mut buffer)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        let row = match std::str::from_utf8(buffer.trim_ascii()) {
+            Ok(row) => row,
+            Err(error) => {
+                return Ok(Some(Row::Invalid(OrderParseError::new(
+                    self.line,
+                    String::from_utf8_lossy(&buffer).trim().to_string(),
+                    OrderParseErrorKind::Unreadable(error.to_string()),
+                ))))
+            }
+        };
+        if row.is_empty()
+            || self
+                .comment
+                .is_some_and(|comment| row.as_bytes()[0] == comment)
+        {
+            return Ok(Some(Row::Skipped));
+        }
+        Ok(Some(match self.parse(row, self.line) {
+            Ok(order) => Row::Order(order),
+            Err(error) => Row::Invalid(error),
+        }))
+    }
+
+    fn header(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Returns the value of the given key of the object, unless it is missing or null.
+fn json_field<'a>(object: &'a Map<String, Value>, name: &str) -> Option<&'a Value> {
+    object.get(name).filter(|value| !value.is_null())
+}
+
+/// Returns the value of the given key of the object as text, if it is a string or a number.
+fn json_text(object: &Map<String, Value>, name: &str) -> Option<String> {
+    match json_field(object, name)? {
+        Value::String(text) => Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Parses the value as a non negative integer, named `field` in the errors.
+fn json_number(value: &Value, field: &'static str) -> Result<u64, OrderParseErrorKind> {
+    value.as_u64().ok_or_else(|| {
+        let value = value.to_string();
+        if value.parse::<i64>().is_ok_and(|number| number < 0) {
+            OrderParseErrorKind::Negative { field, value }
+        } else {
+            OrderParseErrorKind::NotANumber { field, value }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a source of the given rows in the format, with an empty menu.
+    fn source(format: InputFormat, rows: &'static str) -> Box<dyn OrderSource> {
+        format.source(
+            Box::new(rows.as_bytes()),
+            b',',
+            Some(b'#'),
+            None,
+            Arc::new(Menu::default()),
+            None,
+        )
+    }
+
+    /// Reads every row of the source.
+    fn rows(mut source: Box<dyn OrderSource>) -> Vec<Row> {
+        let mut rows = Vec::new();
+        while let Some(row) = source.next_row().expect("Failed to read row") {
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn order(row: &Row) -> &Order {
+        match row {
+            Row::Order(order) => order,
+            _ => panic!("Failed to read an order from the row"),
+        }
+    }
+
+    fn error_kind(row: &Row) -> &OrderParseErrorKind {
+        match row {
+            Row::Invalid(error) => error.get_kind(),
+            _ => panic!("Failed to find an invalid row"),
+        }
+    }
+
+    fn record(fields: &[&str]) -> csv::StringRecord {
+        csv::StringRecord::from(fields.to_vec())
+    }
+
+    #[test]
+    fn the_header_maps_the_columns_by_name_in_any_order() {
+        let columns = ColumnMap::from_header(&record(&["Water", "id", "coffee", " MILK "]))
+            .expect("Failed to find the header")
+            .expect("Failed to map the columns");
+
+        assert_eq!(columns.water, Some(0));
+        assert_eq!(columns.id, Some(1));
+        assert_eq!(columns.coffee, Some(2));
+        assert_eq!(columns.milk, Some(3));
+        assert_eq!(columns.patience, None);
+    }
+
+    #[test]
+    fn a_row_without_known_column_names_is_not_a_header() {
+        assert!(ColumnMap::from_header(&record(&["1", "2", "3"])).is_none());
+    }
+
+    #[test]
+    fn a_header_with_a_repeated_or_missing_column_is_invalid() {
+        assert!(matches!(
+            ColumnMap::from_header(&record(&["coffee", "milk", "water", "coffee"])),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            ColumnMap::from_header(&record(&["coffee", "milk"])),
+            Some(Err(_))
+        ));
+        assert!(matches!(
+            ColumnMap::from_header(&record(&["drink", "size"])),
+            Some(Ok(_))
+        ));
+    }
+
+    #[test]
+    fn a_quoted_field_can_span_several_lines() {
+        let rows = rows(source(
+            InputFormat::Csv,
+            "customer,coffee,milk,water\n\"Ana\nde la caja 2\",1,2,3\nLuis,4,5,6\n",
+        ));
+
+        assert_eq!(rows.len(), 3);
+        assert!(matches!(rows[0], Row::Skipped));
+        let details = order(&rows[1]).get_details();
+        assert_eq!(details.customer.as_deref(), Some("Ana\nde la caja 2"));
+        assert_eq!(*order(&rows[2]).get_water(), 6);
+    }
+
+    #[test]
+    fn a_negative_amount_is_rejected_as_negative() {
+        let rows = rows(source(InputFormat::Csv, "1,-2,3\n"));
+
+        assert!(matches!(
+            error_kind(&rows[0]),
+            OrderParseErrorKind::Negative { value, .. } if value == "-2"
+        ));
+    }
+
+    #[test]
+    fn an_amount_that_is_not_a_number_is_rejected_as_not_a_number() {
+        let rows = rows(source(InputFormat::Csv, "1,2,mucha\n"));
+
+        assert!(matches!(
+            error_kind(&rows[0]),
+            OrderParseErrorKind::NotANumber { value, .. } if value == "mucha"
+        ));
+    }
+
+    #[test]
+    fn the_amounts_given_in_the_order_are_scaled_to_its_size() {
+        let rows = rows(source(
+            InputFormat::Csv,
+            "coffee,milk,water,size\n4,2,1,chico\n4,2,0,grande\n",
+        ));
+
+        let small = order(&rows[1]);
+        assert_eq!(small.get_size(), Size::Small);
+        assert_eq!(
+            [*small.get_coffee(), *small.get_milk(), *small.get_water()],
+            [3, 2, 1]
+        );
+        let large = order(&rows[2]);
+        assert_eq!(
+            [*large.get_coffee(), *large.get_milk(), *large.get_water()],
+            [6, 3, 0]
+        );
+    }
+
+    #[test]
+    fn the_recipe_of_a_json_line_overrides_the_amounts_at_the_top() {
+        let rows = rows(source(
+            InputFormat::Jsonl,
+            "{\"coffee\": 1, \"milk\": 2, \"water\": 3, \"recipe\": {\"coffee\": 9}}\n",
+        ));
+
+        let order = order(&rows[0]);
+        assert_eq!(*order.get_coffee(), 9);
+        assert_eq!(*order.get_milk(), 2);
+        assert_eq!(*order.get_water(), 3);
+    }
+
+    #[test]
+    fn json_lines_report_negative_and_not_a_number_amounts() {
+        let rows = rows(source(
+            InputFormat::Jsonl,
+            "{\"coffee\": -1, \"milk\": 2, \"water\": 3}\n# comentario\n\n{\"coffee\": \"poco\", \"milk\": 2, \"water\": 3}\n",
+        ));
+
+        assert_eq!(rows.len(), 4);
+        assert!(matches!(
+            error_kind(&rows[0]),
+            OrderParseErrorKind::Negative { .. }
+        ));
+        assert!(matches!(rows[1], Row::Skipped));
+        assert!(matches!(rows[2], Row::Skipped));
+        assert!(matches!(
+            error_kind(&rows[3]),
+            OrderParseErrorKind::NotANumber { .. }
+        ));
+        match &rows[3] {
+            Row::Invalid(error) => assert_eq!(error.get_line(), 4),
+            _ => unreachable!(),
+        }
+    }
+}
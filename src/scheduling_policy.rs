@@ -0,0 +1,318 @@
+use crate::dispenser::{Capabilities, Dispenser};
+use crate::order::Order;
+use crate::utils::Message;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Tells which of the main containers are currently being refilled.
+/// Used by the resource-aware policy to avoid orders that would have to wait for a refill.
+pub struct RefillStatus {
+    coffee: AtomicBool,
+    milk: AtomicBool,
+}
+
+impl RefillStatus {
+    /// Creates a new status where no container is being refilled.
+    pub fn new() -> Self {
+        Self {
+            coffee: AtomicBool::new(false),
+            milk: AtomicBool::new(false),
+        }
+    }
+
+    /// Marks whether the ground coffee beans container is being refilled.
+    pub fn set_coffee_refilling(&self, refilling: bool) {
+        self.coffee.store(refilling, Ordering::Relaxed);
+    }
+
+    /// Marks whether the milk foam container is being refilled.
+    pub fn set_milk_refilling(&self, refilling: bool) {
+        self.milk.store(refilling, Ordering::Relaxed);
+    }
+
+    /// Returns True if any of the ingredients of the order is being refilled.
+    pub fn blocks(&self, order: &Order) -> bool {
+        (order.requires_coffee() && self.coffee.load(Ordering::Relaxed))
+            || (order.requires_milk() && self.milk.load(Ordering::Relaxed))
+    }
+}
+
+/// Decides which order of the queue a dispenser takes next.
+pub trait SchedulingPolicy: Send + Sync {
+    /// Returns the index of the order to take among the ones the dispenser can make,
+    /// or `None` if there is no such order.
+    fn select_job(
+        &self,
+        queue: &VecDeque<Message>,
+        refill_status: &RefillStatus,
+        capabilities: &Capabilities,
+    ) -> Option<usize>;
+
+    /// Returns the index of the message for the dispenser to take from the queue.
+    /// Its retire message is taken before any order, while shutdown messages are only taken once
+    /// there are no orders left for the dispenser.
+    fn select(
+        &self,
+        queue: &VecDeque<Message>,
+        refill_status: &RefillStatus,
+        dispenser: &Dispenser,
+    ) -> Option<usize> {
+        retire_position(queue, dispenser)
+            .or_else(|| self.select_job(queue, refill_status, dispenser.get_capabilities()))
+            .or_else(|| {
+                queue
+                    .iter()
+                    .position(|message| matches!(message, Message::Shutdown))
+            })
+    }
+}
+
+/// Returns the index of the message that asks the dispenser to retire, if there is one.
+pub fn retire_position(queue: &VecDeque<Message>, dispenser: &Dispenser) -> Option<usize> {
+    queue
+        .iter()
+        .position(|message| matches!(message, Message::Retire(id) if *id == dispenser.get_id()))
+}
+
+/// Iterates over the orders of the queue the dispenser can make, along with their index.
+fn jobs<'a>(
+    queue: &'a VecDeque<Message>,
+    capabilities: &'a Capabilities,
+) -> impl Iterator<Item = (usize, &'a Order)> {
+    queue
+        .iter()
+        .enumerate()
+        .filter_map(move |(i, message)| match message {
+            Message::Job(order) if capabilities.can_make(order) => Some((i, order)),
+            _ => None,
+        })
+}
+
+/// Takes the orders in the same order they arrived.
+pub struct Fifo;
+
+impl SchedulingPolicy for Fifo {
+    fn select_job(
+        &self,
+        queue: &VecDeque<Message>,
+        _refill_status: &RefillStatus,
+        capabilities: &Capabilities,
+    ) -> Option<usize> {
+        jobs(queue, capabilities).next().map(|(i, _)| i)
+    }
+}
+
+/// Takes the order with the smallest total amount of ingredients, which is the one that
+/// takes less time to prepare.
+pub struct ShortestJobFirst;
+
+impl SchedulingPolicy for ShortestJobFirst {
+    fn select_job(
+        &self,
+        queue: &VecDeque<Message>,
+        _refill_status: &RefillStatus,
+        capabilities: &Capabilities,
+    ) -> Option<usize> {
+        jobs(queue, capabilities)
+            .min_by_key(|(_, order)| order.total_amount())
+            .map(|(i, _)| i)
+    }
+}
+
+/// Takes the order whose deadline is the closest one. Orders without deadline go last.
+pub struct EarliestDeadlineFirst;
+
+impl SchedulingPolicy for EarliestDeadlineFirst {
+    fn select_job(
+        &self,
+        queue: &VecDeque<Message>,
+        _refill_status: &RefillStatus,
+        capabilities: &Capabilities,
+    ) -> Option<usize> {
+        jobs(queue, capabilities)
+            .min_by_key(|(_, order)| (order.deadline().is_none(), order.deadline()))
+            .map(|(i, _)| i)
+    }
+}
+
+/// Takes the first order that does not need an ingredient that is being refilled.
+/// If every order needs one, it behaves like FIFO.
+pub struct ResourceAware;
+
+impl SchedulingPolicy for ResourceAware {
+    fn select_job(
+        &self,
+        queue: &VecDeque<Message>,
+        refill_status: &RefillStatus,
+        capabilities: &Capabilities,
+    ) -> Option<usize> {
+        jobs(queue, capabilities)
+            .find(|(_, order)| !refill_status.blocks(order))
+            .or_else(|| jobs(queue, capabilities).next())
+            .map(|(i, _)| i)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Available scheduling policies, selectable from the command line.
+pub enum PolicyKind {
+    Fifo,
+    ShortestJobFirst,
+    EarliestDeadlineFirst,
+    ResourceAware,
+}
+
+impl PolicyKind {
+    /// Every available policy, in the order they are shown in comparisons.
+    pub const ALL: [PolicyKind; 4] = [
+        PolicyKind::Fifo,
+        PolicyKind::ShortestJobFirst,
+        PolicyKind::EarliestDeadlineFirst,
+        PolicyKind::ResourceAware,
+    ];
+
+    /// Creates the policy represented by this kind.
+    pub fn build(&self) -> Box<dyn SchedulingPolicy> {
+        match self {
+            PolicyKind::Fifo => Box::new(Fifo),
+            PolicyKind::ShortestJobFirst => Box::new(ShortestJobFirst),
+            PolicyKind::EarliestDeadlineFirst => Box::new(EarliestDeadlineFirst),
+            PolicyKind::ResourceAware => Box::new(ResourceAware),
+        }
+    }
+}
+
+impl FromStr for PolicyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fifo" => Ok(PolicyKind::Fifo),
+            "sjf" => Ok(PolicyKind::ShortestJobFirst),
+            "edf" => Ok(PolicyKind::EarliestDeadlineFirst),
+            "resource-aware" => Ok(PolicyKind::ResourceAware),
+            _ => Err(format!(
+                "Política de scheduling desconocida: {} (opciones: fifo, sjf, edf, resource-aware)",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for PolicyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PolicyKind::Fifo => "fifo",
+            PolicyKind::ShortestJobFirst => "sjf",
+            PolicyKind::EarliestDeadlineFirst => "edf",
+            PolicyKind::ResourceAware => "resource-aware",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dispenser::DispenserSpec;
+    use std::time::Duration;
+
+    fn queue(orders: &[Order]) -> VecDeque<Message> {
+        orders.iter().cloned().map(Message::Job).collect()
+    }
+
+    /// Returns the order the policy takes for a dispenser that can make every order.
+    fn select_job(
+        policy: &dyn SchedulingPolicy,
+        queue: &VecDeque<Message>,
+        refill_status: &RefillStatus,
+    ) -> Option<usize> {
+        policy.select_job(queue, refill_status, &Capabilities::all())
+    }
+
+    #[test]
+    fn shortest_job_first_takes_the_order_with_less_ingredients() {
+        let queue = queue(&[
+            Order::new(3, 3, 3, None),
+            Order::new(1, 0, 1, None),
+            Order::new(2, 2, 0, None),
+        ]);
+
+        assert_eq!(
+            select_job(&ShortestJobFirst, &queue, &RefillStatus::new()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn earliest_deadline_first_takes_the_closest_deadline_and_leaves_orders_without_one_last() {
+        let with_deadlines = queue(&[
+            Order::new(1, 1, 1, None),
+            Order::new(1, 1, 1, Some(Duration::from_secs(60))),
+            Order::new(1, 1, 1, Some(Duration::from_secs(5))),
+        ]);
+        assert_eq!(
+            select_job(
+                &EarliestDeadlineFirst,
+                &with_deadlines,
+                &RefillStatus::new()
+            ),
+            Some(2)
+        );
+
+        let without_deadlines = queue(&[Order::new(1, 1, 1, None), Order::new(2, 2, 2, None)]);
+        assert_eq!(
+            select_job(
+                &EarliestDeadlineFirst,
+                &without_deadlines,
+                &RefillStatus::new()
+            ),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn resource_aware_skips_the_orders_whose_ingredient_is_being_refilled() {
+        let queue = queue(&[Order::new(1, 2, 1, None), Order::new(3, 0, 1, None)]);
+        let refill_status = RefillStatus::new();
+        assert_eq!(select_job(&ResourceAware, &queue, &refill_status), Some(0));
+
+        refill_status.set_milk_refilling(true);
+        assert_eq!(select_job(&ResourceAware, &queue, &refill_status), Some(1));
+
+        refill_status.set_coffee_refilling(true);
+        assert_eq!(select_job(&ResourceAware, &queue, &refill_status), Some(0));
+    }
+
+    #[test]
+    fn only_the_orders_the_dispenser_can_make_are_taken() {
+        let queue = queue(&[Order::new(1, 1, 1, None), Order::new(0, 0, 3, None)]);
+        let water_only: Capabilities = "water-only".parse().unwrap();
+
+        assert_eq!(
+            ShortestJobFirst.select_job(&queue, &RefillStatus::new(), &water_only),
+            Some(1)
+        );
+        assert_eq!(
+            Fifo.select_job(&queue, &RefillStatus::new(), &water_only),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn the_retire_message_of_the_dispenser_is_taken_before_orders_and_shutdown_after_them() {
+        let all = Dispenser::new(1, DispenserSpec::default());
+        let no_milk = Dispenser::new(2, "no-milk".parse().unwrap());
+        let mut queue = queue(&[Order::new(1, 2, 1, None)]);
+        queue.push_front(Message::Shutdown);
+        queue.push_back(Message::Retire(2));
+
+        assert_eq!(Fifo.select(&queue, &RefillStatus::new(), &no_milk), Some(2));
+        assert_eq!(Fifo.select(&queue, &RefillStatus::new(), &all), Some(1));
+
+        queue.remove(1);
+        assert_eq!(Fifo.select(&queue, &RefillStatus::new(), &all), Some(0));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::coffee_machine::CoffeeMachine;
+use crate::order::Order;
+use crate::utils::Resource;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::any::Any;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+/// Why the coffee machine stopped before serving every order.
+pub enum StopReason {
+    /// A termination signal arrived
+    Signal(i32),
+    /// One of the threads of the machine failed, either with an error or a panic
+    Failure { actor: String, error: String },
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Signal(signal) => write!(f, "se recibió {}", signal_name(*signal)),
+            StopReason::Failure { actor, error } => write!(f, "falló {}: {}", actor, error),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// How a run of the coffee machine ended.
+pub enum Outcome {
+    /// Every order was read and served
+    Completed,
+    /// The machine stopped early. `drained` tells whether every drink in preparation was
+    /// finished before the drain timeout
+    Stopped { reason: StopReason, drained: bool },
+}
+
+impl Outcome {
+    /// Returns the exit code of the process for this outcome: zero if it completed, 128 plus
+    /// the signal number if it was interrupted, as shells do, and one if a thread failed.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Completed => 0,
+            Outcome::Stopped {
+                reason: StopReason::Signal(signal),
+                ..
+            } => 128 + signal,
+            Outcome::Stopped {
+                reason: StopReason::Failure { .. },
+                ..
+            } => 1,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Completed => write!(f, "ejecución completa"),
+            Outcome::Stopped { reason, drained } => {
+                write!(f, "detenida porque {}", reason)?;
+                if *drained {
+                    write!(f, ", se terminaron todas las bebidas en preparación")
+                } else {
+                    write!(
+                        f,
+                        ", se agotó el tiempo de espera con bebidas en preparación"
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Returns the message of a panic, taken from the payload `catch_unwind` returns.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("pánico sin mensaje")
+    }
+}
+
+/// Returns the name of the given termination signal.
+pub fn signal_name(signal: i32) -> &'static str {
+    match signal {
+        SIGINT => "SIGINT",
+        SIGTERM => "SIGTERM",
+        _ => "una señal",
+    }
+}
+
+/// Spawns a thread that interrupts the coffee machine when a SIGINT or SIGTERM arrives.
+/// Returns an error if the signals could not be registered.
+pub fn handle_signals(coffee_machine: &Arc<CoffeeMachine>) -> Result<(), String> {
+    let mut signals = Signals::new([SIGINT, SIGTERM]).map_err(|error| {
+        format!(
+            "No se pudieron registrar las señales SIGINT y SIGTERM: {}",
+            error
+        )
+    })?;
+    let coffee_machine = coffee_machine.clone();
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            coffee_machine.interrupt(signal);
+        }
+    });
+    Ok(())
+}
+
+/// Writes the orders to a file with the same format the order reader takes, so they can be
+/// served in a later run. It has a header, so the optional information of each order is kept.
+/// Orders of a drink keep the amounts the input gave, if any, along with their size and
+/// modifiers, so the menu works out their recipe again, with its steps and extras. The rest keep
+/// their amounts.
+pub fn persist_orders(orders: &[Order], path: &Path) -> Result<(), String> {
+    let mut writer = csv::WriterBuilder::new()
+        .from_path(path)
+        .map_err(|error| error.to_string())?;
+    writer
+        .write_record([
+            "coffee",
+            "milk",
+            "water",
+            "patience",
+            "id",
+            "customer",
+            "priority",
+            "arrival_time",
+            "size",
+            "drink",
+            "modifiers",
+            "substitutes",
+        ])
+        .map_err(|error| error.to_string())?;
+    let millis = |duration: Option<Duration>| {
+        duration.map_or(String::new(), |duration| duration.as_millis().to_string())
+    };
+    for order in orders {
+        let details = order.get_details();
+        let amount = |ingredient: Resource| match details.requested_amounts[ingredient as usize] {
+            Some(amount) => amount.to_string(),
+            None if details.drink.is_some() => String::new(),
+            None => order.get_amount(ingredient).to_string(),
+        };
+        writer
+            .write_record([
+                amount(Resource::Coffee),
+                amount(Resource::Milk),
+                amount(Resource::Water),
+                millis(order.get_patience()),
+                details.id.clone().unwrap_or_default(),
+                details.customer.clone().unwrap_or_default(),
+                details
+                    .priority
+                    .map_or(String::new(), |priority| priority.to_string()),
+                millis(details.arrival_time),
+                details.size.clone().unwrap_or_default(),
+                details.drink.clone().unwrap_or_default(),
+                details.modifiers.join("+"),
+                details.substitutes.join("+"),
+            ])
+            .map_err(|error| error.to_string())?;
+    }
+    writer.flush().map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::menu::Menu;
+    use crate::order_source::{InputFormat, Row};
+    use std::fs::{self, File};
+    use std::process;
+
+    /// Reads the orders of the CSV rows, taking the drinks from the menu.
+    fn read(rows: Box<dyn std::io::Read + Send>, menu: &Arc<Menu>) -> Vec<Order> {
+        let mut source = InputFormat::Csv.source(rows, b',', None, None, menu.clone(), None);
+        let mut orders = Vec::new();
+        while let Some(row) = source.next_row().expect("Failed to read row") {
+            if let Row::Order(order) = row {
+                orders.push(order);
+            }
+        }
+        orders
+    }
+
+    #[test]
+    fn persisted_orders_replay_with_their_modifiers() {
+        let menu: Arc<Menu> = Arc::new(
+            serde_json::from_str(
+                r#"{
+                    "drinks": { "latte": { "coffee": 2, "milk": 4, "water": 1, "steps": ["milk", "coffee"] } },
+                    "modifiers": { "extra shot": { "add": { "coffee": 1 } }, "canela": { "extras": ["canela"] } }
+                }"#,
+            )
+            .expect("Failed to parse menu"),
+        );
+        let mut orders = read(
+            Box::new("drink,size,water,modifiers\nlatte,grande,5,extra shot+canela\n".as_bytes()),
+            &menu,
+        );
+        orders.push(Order::new(1, 2, 3, None));
+        let path = std::env::temp_dir().join(format!("unstarted-orders-{}.csv", process::id()));
+
+        persist_orders(&orders, &path).expect("Failed to persist orders");
+        let replayed = read(
+            Box::new(File::open(&path).expect("Failed to open orders")),
+            &menu,
+        );
+        fs::remove_file(&path).expect("Failed to remove orders");
+
+        assert_eq!(replayed.len(), 2);
+        for (order, replayed) in orders.iter().zip(&replayed) {
+            for ingredient in Resource::ALL {
+                assert_eq!(
+                    order.get_amount(ingredient),
+                    replayed.get_amount(ingredient)
+                );
+            }
+            assert_eq!(order.get_steps(), replayed.get_steps());
+            assert_eq!(order.get_extras(), replayed.get_extras());
+            assert_eq!(order.get_size(), replayed.get_size());
+        }
+        assert_eq!(
+            replayed[0].get_details().modifiers,
+            ["extra shot", "canela"]
+        );
+        assert_eq!(*replayed[0].get_coffee(), 4);
+        assert_eq!(*replayed[0].get_water(), 8);
+    }
+}
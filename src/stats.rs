@@ -0,0 +1,929 @@
+use crate::alert::Severity;
+use crate::constants::{COLOR_RESET, COLOR_YELLOW};
+use crate::delivery::Delivery;
+use crate::order::{Order, Size};
+use crate::order_reader::InvalidOrderPolicy;
+use crate::scheduling_policy::PolicyKind;
+use crate::supplier::PurchaseOrder;
+use crate::utils::Resource;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Collects the latency (time since arrival until the drink is ready) of every order served.
+pub struct LatencyStats {
+    latencies: Vec<Duration>,
+}
+
+impl LatencyStats {
+    /// Creates empty latency stats.
+    pub fn new() -> Self {
+        Self {
+            latencies: Vec::new(),
+        }
+    }
+
+    /// Records the latency of a served order.
+    pub fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency);
+    }
+
+    /// Returns a summary with the mean and tail latencies recorded so far.
+    pub fn summary(&self) -> LatencySummary {
+        let mut sorted = self.latencies.clone();
+        sorted.sort();
+        let count = sorted.len();
+        let mean = if count == 0 {
+            Duration::ZERO
+        } else {
+            sorted.iter().sum::<Duration>() / count as u32
+        };
+        LatencySummary {
+            count,
+            mean,
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns the given percentile of a sorted slice, using the nearest-rank method.
+fn percentile(sorted: &[Duration], percentile: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Clone, Copy)]
+/// Mean and tail latencies of the orders served by the coffee machine.
+pub struct LatencySummary {
+    pub count: usize,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Display for LatencySummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Latencia ({} pedidos) - media: {:.2}s | p50: {:.2}s | p95: {:.2}s | p99: {:.2}s | máx: {:.2}s",
+            self.count,
+            self.mean.as_secs_f64(),
+            self.p50.as_secs_f64(),
+            self.p95.as_secs_f64(),
+            self.p99.as_secs_f64(),
+            self.max.as_secs_f64()
+        )
+    }
+}
+
+/// Builds a table comparing the latencies obtained by each policy on the same input.
+pub fn policy_comparison(results: &[(PolicyKind, LatencySummary)]) -> String {
+    let mut report = format!(
+        "{}[Comparación de políticas]{}\n{:<16}{:>10}{:>10}{:>10}{:>10}{:>10}\n",
+        COLOR_YELLOW, COLOR_RESET, "política", "media", "p50", "p95", "p99", "máx"
+    );
+    for (policy, summary) in results {
+        report.push_str(&format!(
+            "{:<16}{:>9.2}s{:>9.2}s{:>9.2}s{:>9.2}s{:>9.2}s\n",
+            policy.to_string(),
+            summary.mean.as_secs_f64(),
+            summary.p50.as_secs_f64(),
+            summary.p95.as_secs_f64(),
+            summary.p99.as_secs_f64(),
+            summary.max.as_secs_f64()
+        ));
+    }
+    report
+}
+
+/// Keeps track of how many orders were served within their deadline.
+pub struct SlaStats {
+    on_time: u64,
+    late: u64,
+    abandoned: u64,
+    wasted_coffee: u64,
+    wasted_milk: u64,
+    wasted_water: u64,
+}
+
+impl SlaStats {
+    /// Creates empty SLA stats.
+    pub fn new() -> Self {
+        Self {
+            on_time: 0,
+            late: 0,
+            abandoned: 0,
+            wasted_coffee: 0,
+            wasted_milk: 0,
+            wasted_water: 0,
+        }
+    }
+
+    /// Records an order that was served before its deadline.
+    pub fn record_on_time(&mut self) {
+        self.on_time += 1;
+    }
+
+    /// Records an order that was served after its deadline.
+    /// Its ingredients are considered wasted, since the customer is already gone.
+    pub fn record_late(&mut self, order: &Order) {
+        self.late += 1;
+        self.wasted_coffee += order.get_coffee();
+        self.wasted_milk += order.get_milk();
+        self.wasted_water += order.get_water();
+    }
+
+    /// Records an order whose customer left before it was taken by a dispenser.
+    pub fn record_abandoned(&mut self) {
+        self.abandoned += 1;
+    }
+
+    /// Returns the percentage of orders with deadline that were served on time.
+    pub fn attainment(&self) -> f64 {
+        let total = self.on_time + self.late + self.abandoned;
+        if total == 0 {
+            return 100.0;
+        }
+        self.on_time as f64 * 100.0 / total as f64
+    }
+}
+
+impl Display for SlaStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "SLA cumplido: {:.1}% || A tiempo: {} - Tarde: {} - Abandonados: {} || Desperdiciado en bebidas tardías: café {} - leche {} - agua {}",
+            self.attainment(),
+            self.on_time,
+            self.late,
+            self.abandoned,
+            self.wasted_coffee,
+            self.wasted_milk,
+            self.wasted_water
+        )
+    }
+}
+
+/// Work done by a single dispenser.
+pub struct DispenserStats {
+    drinks_prepared: u64,
+    busy_time: Duration,
+    retired: bool,
+    crashes: u64,
+    out_of_service: bool,
+}
+
+impl DispenserStats {
+    /// Creates empty dispenser stats.
+    pub fn new() -> Self {
+        Self {
+            drinks_prepared: 0,
+            busy_time: Duration::ZERO,
+            retired: false,
+            crashes: 0,
+            out_of_service: false,
+        }
+    }
+
+    /// Records a drink prepared by the dispenser and the time it took.
+    pub fn record(&mut self, preparation_time: Duration) {
+        self.drinks_prepared += 1;
+        self.busy_time += preparation_time;
+    }
+
+    /// Marks the dispenser as retired from the pool.
+    pub fn mark_retired(&mut self) {
+        self.retired = true;
+    }
+
+    /// Records a crash of the dispenser. Returns how many times it crashed.
+    pub fn record_crash(&mut self) -> u64 {
+        self.crashes += 1;
+        self.crashes
+    }
+
+    /// Marks the dispenser as out of service, after crashing too many times.
+    pub fn mark_out_of_service(&mut self) {
+        self.out_of_service = true;
+    }
+}
+
+impl Display for DispenserStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bebidas preparadas: {} - Tiempo ocupado: {:.2}s{}",
+            self.drinks_prepared,
+            self.busy_time.as_secs_f64(),
+            if self.retired { " (retirado)" } else { "" }
+        )?;
+        if self.crashes > 0 {
+            write!(f, " - Caídas: {}", self.crashes)?;
+        }
+        if self.out_of_service {
+            write!(f, " (fuera de servicio)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Rows of the input that were not valid orders, by what was done with them.
+pub struct RejectStats {
+    skipped: u64,
+    stopped: u64,
+    quarantined: u64,
+}
+
+impl RejectStats {
+    /// Creates empty reject stats.
+    pub fn new() -> Self {
+        Self {
+            skipped: 0,
+            stopped: 0,
+            quarantined: 0,
+        }
+    }
+
+    /// Records a rejected row, handled with the given policy.
+    pub fn record(&mut self, policy: InvalidOrderPolicy) {
+        match policy {
+            InvalidOrderPolicy::Skip => self.skipped += 1,
+            InvalidOrderPolicy::Stop => self.stopped += 1,
+            InvalidOrderPolicy::Quarantine => self.quarantined += 1,
+        }
+    }
+
+    /// Returns the number of rejected rows.
+    pub fn total(&self) -> u64 {
+        self.skipped + self.stopped + self.quarantined
+    }
+
+    /// Returns the number of rows written to the rejects file.
+    pub fn get_quarantined(&self) -> u64 {
+        self.quarantined
+    }
+}
+
+impl Display for RejectStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Pedidos rechazados: {} - Descartados: {} - En cuarentena: {} - Detuvieron la lectura: {}",
+            self.total(),
+            self.skipped,
+            self.quarantined,
+            self.stopped
+        )
+    }
+}
+
+/// Crashes of the dispensers and how the supervisor handled them.
+pub struct CrashStats {
+    crashes: u64,
+    restarts: u64,
+    requeued: u64,
+    discarded: u64,
+}
+
+impl CrashStats {
+    /// Creates empty crash stats.
+    pub fn new() -> Self {
+        Self {
+            crashes: 0,
+            restarts: 0,
+            requeued: 0,
+            discarded: 0,
+        }
+    }
+
+    /// Records a crash of a dispenser.
+    pub fn record_crash(&mut self) {
+        self.crashes += 1;
+    }
+
+    /// Records the restart of a crashed dispenser.
+    pub fn record_restart(&mut self) {
+        self.restarts += 1;
+    }
+
+    /// Records an order that was in preparation during a crash and was queued again.
+    pub fn record_requeued(&mut self) {
+        self.requeued += 1;
+    }
+
+    /// Records an order that was in preparation during a crash and was discarded.
+    pub fn record_discarded(&mut self) {
+        self.discarded += 1;
+    }
+
+    /// Returns True if any dispenser crashed.
+    pub fn has_crashes(&self) -> bool {
+        self.crashes > 0
+    }
+}
+
+impl Display for CrashStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Caídas de dispensers: {} - Reinicios: {} - Pedidos reencolados: {} - Pedidos descartados: {}",
+            self.crashes, self.restarts, self.requeued, self.discarded
+        )
+    }
+}
+
+/// Time spent applying each ingredient, and what was saved applying them at the same time.
+pub struct StepStats {
+    steps: [(u64, Duration); 3],
+    parallel_steps: u64,
+    saved: Duration,
+}
+
+impl StepStats {
+    /// Creates empty step stats.
+    pub fn new() -> Self {
+        Self {
+            steps: [(0, Duration::ZERO); 3],
+            parallel_steps: 0,
+            saved: Duration::ZERO,
+        }
+    }
+
+    /// Records the time it took to apply an ingredient.
+    pub fn record(&mut self, ingredient: Resource, elapsed: Duration) {
+        let (count, total) = &mut self.steps[ingredient as usize];
+        *count += 1;
+        *total += elapsed;
+    }
+
+    /// Records a step whose ingredients were applied at the same time, given how long it took
+    /// and how long applying them one after the other would have taken.
+    pub fn record_parallel(&mut self, elapsed: Duration, sequential: Duration) {
+        self.parallel_steps += 1;
+        self.saved += sequential.saturating_sub(elapsed);
+    }
+}
+
+impl Display for StepStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Tiempos por paso:")?;
+        for ingredient in Resource::ALL {
+            let (count, total) = self.steps[ingredient as usize];
+            let average = if count == 0 {
+                0.0
+            } else {
+                total.as_secs_f64() / count as f64
+            };
+            write!(
+                f,
+                " {}: {} ({:.2}s promedio) -",
+                ingredient.field_name(),
+                count,
+                average
+            )?;
+        }
+        write!(
+            f,
+            " Pasos en paralelo: {} (ahorraron {:.2}s)",
+            self.parallel_steps,
+            self.saved.as_secs_f64()
+        )
+    }
+}
+
+/// Drinks served, by the name they were ordered with.
+pub struct DrinkStats {
+    by_name: BTreeMap<String, u64>,
+    modified: u64,
+    custom: u64,
+}
+
+impl DrinkStats {
+    /// Creates empty drink stats.
+    pub fn new() -> Self {
+        Self {
+            by_name: BTreeMap::new(),
+            modified: 0,
+            custom: 0,
+        }
+    }
+
+    /// Records a served order, under the name of its drink if it has one.
+    pub fn record(&mut self, order: &Order) {
+        let details = order.get_details();
+        match &details.drink {
+            Some(drink) => *self.by_name.entry(drink.to_lowercase()).or_insert(0) += 1,
+            None => self.custom += 1,
+        }
+        if !details.modifiers.is_empty() {
+            self.modified += 1;
+        }
+    }
+
+    /// Returns True if no drink was ordered by name.
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+impl Display for DrinkStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bebidas servidas:")?;
+        for (drink, count) in &self.by_name {
+            write!(f, " {}: {} -", drink, count)?;
+        }
+        write!(
+            f,
+            " a medida: {} - Con modificadores: {}",
+            self.custom, self.modified
+        )
+    }
+}
+
+/// Drinks served by size, and the ones that could not be served for lack of cups.
+pub struct SizeStats {
+    sold: [u64; 3],
+    out_of_cups: [u64; 3],
+}
+
+impl SizeStats {
+    /// Creates empty size stats.
+    pub fn new() -> Self {
+        Self {
+            sold: [0; 3],
+            out_of_cups: [0; 3],
+        }
+    }
+
+    /// Records a drink served in a cup of the given size.
+    pub fn record_sold(&mut self, size: Size) {
+        self.sold[size as usize] += 1;
+    }
+
+    /// Records an order discarded because there were no cups of its size.
+    pub fn record_out_of_cups(&mut self, size: Size) {
+        self.out_of_cups[size as usize] += 1;
+    }
+}
+
+impl Display for SizeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let by_size = |counts: &[u64; 3]| {
+            Size::ALL
+                .iter()
+                .map(|size| format!("{}: {}", size, counts[*size as usize]))
+                .collect::<Vec<String>>()
+                .join(" - ")
+        };
+        write!(
+            f,
+            "Ventas por tamaño: {} || Sin vaso: {}",
+            by_size(&self.sold),
+            by_size(&self.out_of_cups)
+        )
+    }
+}
+
+/// Drinks served with a substitute instead of an ingredient that ran out, and the ones
+/// discarded because neither was available.
+pub struct SubstitutionStats {
+    by_substitute: BTreeMap<String, u64>,
+    substituted: u64,
+    declined: u64,
+    unavailable: u64,
+}
+
+impl SubstitutionStats {
+    /// Creates empty substitution stats.
+    pub fn new() -> Self {
+        Self {
+            by_substitute: BTreeMap::new(),
+            substituted: 0,
+            declined: 0,
+            unavailable: 0,
+        }
+    }
+
+    /// Records a served order, if any of its ingredients was replaced.
+    pub fn record(&mut self, order: &Order) {
+        if order.get_substitutions().is_empty() {
+            return;
+        }
+        self.substituted += 1;
+        for (_, name) in order.get_substitutions() {
+            *self.by_substitute.entry(name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    /// Records an order discarded for lack of an ingredient. `declined` is True if there was a
+    /// substitute the customer did not accept.
+    pub fn record_discarded(&mut self, declined: bool) {
+        if declined {
+            self.declined += 1;
+        } else {
+            self.unavailable += 1;
+        }
+    }
+
+    /// Returns True if no order was substituted or discarded for lack of an ingredient.
+    pub fn is_empty(&self) -> bool {
+        self.substituted == 0 && self.declined == 0 && self.unavailable == 0
+    }
+}
+
+impl Display for SubstitutionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bebidas con sustituciones: {}", self.substituted)?;
+        for (name, count) in &self.by_substitute {
+            write!(f, " - {}: {}", name, count)?;
+        }
+        write!(
+            f,
+            " || Descartadas por falta de ingredientes: {} (rechazaron el sustituto: {})",
+            self.unavailable + self.declined,
+            self.declined
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+/// Orders of a single source.
+struct SourceCounts {
+    taken: u64,
+    rejected: u64,
+    served: u64,
+}
+
+/// Orders taken, rejected and served by each named source, like the registers of the café.
+pub struct SourceStats {
+    by_source: BTreeMap<String, SourceCounts>,
+}
+
+impl SourceStats {
+    /// Creates empty source stats.
+    pub fn new() -> Self {
+        Self {
+            by_source: BTreeMap::new(),
+        }
+    }
+
+    /// Records an order taken from the source.
+    pub fn record_taken(&mut self, source: &str) {
+        self.counts(source).taken += 1;
+    }
+
+    /// Records a row of the source that was not a valid order.
+    pub fn record_rejected(&mut self, source: &str) {
+        self.counts(source).rejected += 1;
+    }
+
+    /// Records a served order, under its source if it has one.
+    pub fn record_served(&mut self, order: &Order) {
+        if let Some(source) = &order.get_details().source {
+            self.counts(source).served += 1;
+        }
+    }
+
+    /// Returns True if no named source had any order.
+    pub fn is_empty(&self) -> bool {
+        self.by_source.is_empty()
+    }
+
+    fn counts(&mut self, source: &str) -> &mut SourceCounts {
+        self.by_source.entry(source.to_string()).or_default()
+    }
+}
+
+impl Display for SourceStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pedidos por origen:")?;
+        let sources: Vec<String> = self
+            .by_source
+            .iter()
+            .map(|(source, counts)| {
+                format!(
+                    " {}: tomados {} - rechazados {} - servidos {}",
+                    source, counts.taken, counts.rejected, counts.served
+                )
+            })
+            .collect();
+        write!(f, "{}", sources.join(" ||"))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Direction of a change in the size of the dispenser pool.
+pub enum ScaleAction {
+    Up,
+    Down,
+}
+
+/// A change in the size of the dispenser pool.
+pub struct ScaleEvent {
+    /// Time since the coffee machine started
+    pub elapsed: Duration,
+    pub action: ScaleAction,
+    /// Active dispensers after the change
+    pub active_dispensers: usize,
+    pub reason: String,
+}
+
+impl Display for ScaleEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action {
+            ScaleAction::Up => "agregado",
+            ScaleAction::Down => "retirado",
+        };
+        write!(
+            f,
+            "{:>7.2}s - Dispenser {} ({} activos) - {}",
+            self.elapsed.as_secs_f64(),
+            action,
+            self.active_dispensers,
+            self.reason
+        )
+    }
+}
+
+/// Counts how many times the threads waiting on a condition were woken up.
+/// A wakeup is useful when the thread stops waiting, and spurious when it goes back to wait.
+pub struct WakeupStats {
+    useful: AtomicU64,
+    spurious: AtomicU64,
+}
+
+impl WakeupStats {
+    /// Creates empty wakeup stats.
+    pub fn new() -> Self {
+        Self {
+            useful: AtomicU64::new(0),
+            spurious: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a wakeup, which is useful if the thread stopped waiting.
+    pub fn record(&self, useful: bool) {
+        let counter = if useful { &self.useful } else { &self.spurious };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Display for WakeupStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "útiles {} - espurios {}",
+            self.useful.load(Ordering::Relaxed),
+            self.spurious.load(Ordering::Relaxed)
+        )
+    }
+}
+
+/// Low level alerts raised for a resource.
+pub struct AlertStats {
+    raised: [u64; 3],
+    reminders: u64,
+    cleared: u64,
+    forecasts: u64,
+}
+
+impl AlertStats {
+    /// Creates empty alert stats.
+    pub fn new() -> Self {
+        Self {
+            raised: [0; 3],
+            reminders: 0,
+            cleared: 0,
+            forecasts: 0,
+        }
+    }
+
+    /// Records an alert raised because the level went under the threshold of the given severity.
+    pub fn record_raised(&mut self, severity: Severity) {
+        self.raised[severity as usize] += 1;
+    }
+
+    /// Records a reminder of an alert that was not cleared yet.
+    pub fn record_reminder(&mut self) {
+        self.reminders += 1;
+    }
+
+    /// Records an alert cleared because the level went back above the threshold.
+    pub fn record_cleared(&mut self) {
+        self.cleared += 1;
+    }
+
+    /// Records an alert raised because the container is expected to run out soon.
+    pub fn record_forecast(&mut self) {
+        self.forecasts += 1;
+    }
+}
+
+impl Display for AlertStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for severity in Severity::ALL {
+            write!(f, "{}: {} - ", severity, self.raised[severity as usize])?;
+        }
+        write!(
+            f,
+            "Recordatorios: {} - Resueltas: {} - Agotamientos previstos: {}",
+            self.reminders, self.cleared, self.forecasts
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+/// Deliveries of alerts to a sink.
+pub struct SinkStats {
+    delivered: u64,
+    retries: u64,
+    failed: u64,
+    rate_limited: u64,
+}
+
+impl SinkStats {
+    /// Creates empty sink stats.
+    pub fn new() -> Self {
+        Self {
+            delivered: 0,
+            retries: 0,
+            failed: 0,
+            rate_limited: 0,
+        }
+    }
+
+    /// Records an alert delivered to the sink.
+    pub fn record_delivered(&mut self) {
+        self.delivered += 1;
+    }
+
+    /// Records a new attempt to deliver an alert after a failure.
+    pub fn record_retry(&mut self) {
+        self.retries += 1;
+    }
+
+    /// Records an alert that could not be delivered after every retry.
+    pub fn record_failed(&mut self) {
+        self.failed += 1;
+    }
+
+    /// Records an alert dropped because of the rate limit.
+    pub fn record_rate_limited(&mut self) {
+        self.rate_limited += 1;
+    }
+}
+
+impl Display for SinkStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Entregadas: {} - Reintentos: {} - Fallidas: {} - Descartadas por límite: {}",
+            self.delivered, self.retries, self.failed, self.rate_limited
+        )
+    }
+}
+
+/// Delivery received by the machine.
+pub struct DeliveryRecord {
+    delivery: Delivery,
+    elapsed: Duration,
+    accepted: u64,
+}
+
+impl DeliveryRecord {
+    /// Creates the record of a delivery received `elapsed` after the machine started, of which
+    /// only `accepted` fit in the container.
+    pub fn new(delivery: Delivery, elapsed: Duration, accepted: u64) -> Self {
+        Self {
+            delivery,
+            elapsed,
+            accepted,
+        }
+    }
+}
+
+impl Display for DeliveryRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}s - {}: +{} de {} entregados (descartados por capacidad: {})",
+            self.elapsed.as_secs_f64(),
+            self.delivery.get_container(),
+            self.accepted,
+            self.delivery.get_amount(),
+            self.delivery.get_amount() - self.accepted
+        )
+    }
+}
+
+/// What happened to a purchase order.
+pub enum PurchaseStatus {
+    /// The supplier rejected it, for the given reason
+    Rejected(String),
+    /// The supplier committed to deliver the given amount, which has not arrived yet
+    Pending(u64),
+    /// The delivery arrived, with the amount the supplier committed to and the amount that fit
+    /// in the container
+    Received { promised: u64, accepted: u64 },
+}
+
+/// Purchase order placed by the machine, along with what happened to it.
+pub struct PurchaseRecord {
+    order: PurchaseOrder,
+    status: PurchaseStatus,
+}
+
+impl PurchaseRecord {
+    /// Creates the record of a purchase order.
+    pub fn new(order: PurchaseOrder, status: PurchaseStatus) -> Self {
+        Self { order, status }
+    }
+
+    /// Returns the purchase order.
+    pub fn get_order(&self) -> &PurchaseOrder {
+        &self.order
+    }
+
+    /// Returns True if the supplier accepted the order and its delivery did not arrive yet.
+    pub fn is_pending(&self) -> bool {
+        matches!(self.status, PurchaseStatus::Pending(_))
+    }
+
+    /// Records the arrival of the delivery of the order.
+    pub fn record_received(&mut self, accepted: u64) {
+        if let PurchaseStatus::Pending(promised) = self.status {
+            self.status = PurchaseStatus::Received { promised, accepted };
+        }
+    }
+}
+
+impl Display for PurchaseRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - ", self.order)?;
+        match &self.status {
+            PurchaseStatus::Rejected(reason) => write!(f, "rechazada: {}", reason),
+            PurchaseStatus::Pending(promised) => {
+                write!(f, "pendiente: no llegaron {} antes del cierre", promised)
+            }
+            PurchaseStatus::Received { promised, accepted } => {
+                write!(f, "recibida: {} de lo pedido", promised)?;
+                if *promised < self.order.get_quantity() {
+                    write!(f, " (parcial)")?;
+                }
+                write!(f, ", se agregaron {}", accepted)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latencies(millis: &[u64]) -> LatencyStats {
+        let mut stats = LatencyStats::new();
+        for latency in millis {
+            stats.record(Duration::from_millis(*latency));
+        }
+        stats
+    }
+
+    #[test]
+    fn the_summary_has_the_mean_and_the_nearest_rank_percentiles() {
+        let summary = latencies(&[400, 100, 300, 200, 1000]).summary();
+
+        assert_eq!(summary.count, 5);
+        assert_eq!(summary.mean, Duration::from_millis(400));
+        assert_eq!(summary.p50, Duration::from_millis(300));
+        assert_eq!(summary.p95, Duration::from_millis(1000));
+        assert_eq!(summary.max, Duration::from_millis(1000));
+        assert_eq!(LatencyStats::new().summary().p99, Duration::ZERO);
+    }
+
+    #[test]
+    fn the_policy_comparison_has_a_row_per_policy_in_the_given_order() {
+        let results = [
+            (PolicyKind::ShortestJobFirst, latencies(&[1500]).summary()),
+            (PolicyKind::Fifo, latencies(&[250, 750]).summary()),
+        ];
+
+        let report = policy_comparison(&results);
+        let rows: Vec<&str> = report.lines().skip(2).collect();
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("sjf"));
+        assert!(rows[0].contains("1.50s"));
+        assert!(rows[1].starts_with("fifo"));
+        assert!(rows[1].contains("0.50s"));
+    }
+}
@@ -0,0 +1,198 @@
+use crate::container::Container;
+use crate::utils::Resource;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Value of the order field that accepts any substitute.
+pub const ANY_SUBSTITUTE: &str = "*";
+
+#[derive(Clone, Debug)]
+/// An ingredient that can replace another one when it runs out, like oat milk for milk.
+pub struct SubstituteSpec {
+    pub ingredient: Resource,
+    pub name: String,
+    /// Amount of the substitute the machine starts with
+    pub stock: u64,
+}
+
+impl FromStr for SubstituteSpec {
+    type Err = String;
+
+    /// Accepts `<ingredient>=<substitute>:<stock>`, for example `milk=avena:500`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Sustitución inválida: {} (se esperaba <ingrediente>=<sustituto>:<cantidad>)",
+                s
+            )
+        };
+        let (ingredient, rest) = s.split_once('=').ok_or_else(invalid)?;
+        let (name, stock) = rest.rsplit_once(':').ok_or_else(invalid)?;
+        let ingredient: Resource = ingredient.parse()?;
+        if ingredient == Resource::Water {
+            return Err(String::from("El agua no se agota, no necesita sustituto"));
+        }
+        let name = name.trim().to_lowercase();
+        if name.is_empty() || name == ANY_SUBSTITUTE {
+            return Err(invalid());
+        }
+        Ok(Self {
+            ingredient,
+            name,
+            stock: stock.trim().parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Result of trying to replace an ingredient of an order.
+pub enum Reservation {
+    /// The amount of the substitute with this name was set aside for the order
+    Reserved(String),
+    /// There was enough of a substitute, but the customer did not accept any of them
+    Declined,
+    /// No substitute of the ingredient had enough stock
+    Unavailable,
+}
+
+/// Stock of the substitutes, shared by the dispensers.
+pub struct Substitutes {
+    substitutes: Vec<(SubstituteSpec, Mutex<Container>)>,
+}
+
+impl Substitutes {
+    /// Creates the substitutes with their initial stock, tried in the given order.
+    pub fn new(specs: &[SubstituteSpec]) -> Self {
+        Self {
+            substitutes: specs
+                .iter()
+                .map(|spec| (spec.clone(), Mutex::new(Container::new(spec.stock))))
+                .collect(),
+        }
+    }
+
+    /// Returns True if no substitute was configured.
+    pub fn is_empty(&self) -> bool {
+        self.substitutes.is_empty()
+    }
+
+    /// Sets aside the amount of the first substitute of the ingredient that the customer
+    /// accepts and has enough stock.
+    pub fn reserve(&self, ingredient: Resource, amount: u64, accepted: &[String]) -> Reservation {
+        let mut declined = false;
+        for (spec, stock) in &self.substitutes {
+            if spec.ingredient != ingredient {
+                continue;
+            }
+            let mut stock = stock.lock().expect("Failed to lock substitute");
+            if !stock.has_enough(&amount) {
+                continue;
+            }
+            if !accepts(accepted, &spec.name) {
+                declined = true;
+                continue;
+            }
+            stock.subtract(&amount);
+            return Reservation::Reserved(spec.name.clone());
+        }
+        if declined {
+            Reservation::Declined
+        } else {
+            Reservation::Unavailable
+        }
+    }
+
+    /// Gives back an amount reserved for an order that was not prepared.
+    pub fn release(&self, name: &str, amount: u64) {
+        if let Some((_, stock)) = self.substitutes.iter().find(|(spec, _)| spec.name == name) {
+            stock
+                .lock()
+                .expect("Failed to lock substitute")
+                .add(&amount);
+        }
+    }
+
+    /// Returns the name and the current stock of each substitute.
+    pub fn stock(&self) -> Vec<(String, u64)> {
+        self.substitutes
+            .iter()
+            .map(|(spec, stock)| {
+                let stock = stock.lock().expect("Failed to lock substitute");
+                (spec.name.clone(), *stock.get_current_amount())
+            })
+            .collect()
+    }
+}
+
+/// Returns True if the list of substitutes the customer accepts includes the given one.
+fn accepts(accepted: &[String], name: &str) -> bool {
+    accepted
+        .iter()
+        .any(|accepted| accepted == ANY_SUBSTITUTE || accepted.to_lowercase() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn substitutes() -> Substitutes {
+        let specs: Vec<SubstituteSpec> = ["milk=avena:5", "milk=almendra:20"]
+            .iter()
+            .map(|spec| spec.parse().expect("Failed to parse substitute"))
+            .collect();
+        Substitutes::new(&specs)
+    }
+
+    fn accepted(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn the_first_accepted_substitute_with_enough_stock_is_reserved() {
+        let substitutes = substitutes();
+
+        assert_eq!(
+            substitutes.reserve(Resource::Milk, 10, &accepted(&["*"])),
+            Reservation::Reserved(String::from("almendra"))
+        );
+        assert_eq!(
+            substitutes.reserve(Resource::Milk, 5, &accepted(&["Avena"])),
+            Reservation::Reserved(String::from("avena"))
+        );
+        assert_eq!(
+            substitutes.stock(),
+            [(String::from("avena"), 0), (String::from("almendra"), 10)]
+        );
+    }
+
+    #[test]
+    fn a_substitute_the_customer_does_not_accept_is_declined() {
+        let substitutes = substitutes();
+
+        assert_eq!(
+            substitutes.reserve(Resource::Milk, 5, &accepted(&["soja"])),
+            Reservation::Declined
+        );
+        assert_eq!(
+            substitutes.reserve(Resource::Milk, 50, &accepted(&["*"])),
+            Reservation::Unavailable
+        );
+        assert_eq!(
+            substitutes.reserve(Resource::Coffee, 1, &accepted(&["*"])),
+            Reservation::Unavailable
+        );
+    }
+
+    #[test]
+    fn a_released_substitute_can_be_reserved_again() {
+        let substitutes = substitutes();
+        substitutes.reserve(Resource::Milk, 5, &accepted(&["avena"]));
+
+        substitutes.release("avena", 5);
+
+        assert_eq!(
+            substitutes.reserve(Resource::Milk, 5, &accepted(&["avena"])),
+            Reservation::Reserved(String::from("avena"))
+        );
+    }
+}
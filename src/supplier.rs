@@ -0,0 +1,190 @@
+use crate::constants::MOCK_SUPPLIER_MIN_PARTIAL_FILL;
+use crate::container::ContainerKind;
+use crate::delivery::Delivery;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug)]
+/// Request to a supplier for more stock of a raw material.
+pub struct PurchaseOrder {
+    id: u64,
+    container: ContainerKind,
+    quantity: u64,
+    placed_at: Duration,
+}
+
+impl PurchaseOrder {
+    /// Creates a purchase order placed `placed_at` after the machine started.
+    pub fn new(id: u64, container: ContainerKind, quantity: u64, placed_at: Duration) -> Self {
+        Self {
+            id,
+            container,
+            quantity,
+            placed_at,
+        }
+    }
+
+    /// Returns the number that identifies the purchase order.
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the container the stock is for.
+    pub fn get_container(&self) -> ContainerKind {
+        self.container
+    }
+
+    /// Returns the amount requested.
+    pub fn get_quantity(&self) -> u64 {
+        self.quantity
+    }
+
+    /// Returns the time since the start of the machine when the order was placed.
+    pub fn get_placed_at(&self) -> Duration {
+        self.placed_at
+    }
+}
+
+impl Display for PurchaseOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Orden de compra #{} ({:.1}s): {} de {}",
+            self.id,
+            self.placed_at.as_secs_f64(),
+            self.quantity,
+            self.container
+        )
+    }
+}
+
+/// Provides the raw materials of the machine.
+pub trait Supplier: Send + Sync {
+    /// Places a purchase order. Returns the delivery the supplier commits to, which may be for
+    /// less than the requested quantity, or the reason why the order was rejected.
+    fn place_order(&self, order: &PurchaseOrder) -> Result<Delivery, String>;
+
+    /// Returns the usual time between placing an order and receiving it.
+    fn lead_time(&self) -> Duration;
+}
+
+/// Local supplier that delivers after a fixed lead time, and that can reject orders or fulfil
+/// them partially at random.
+pub struct MockSupplier {
+    lead_time: Duration,
+    failure_rate: f64,
+    partial_rate: f64,
+    rng: Mutex<StdRng>,
+}
+
+impl MockSupplier {
+    /// Creates a supplier that rejects a `failure_rate` fraction of the orders and only delivers
+    /// part of a `partial_rate` fraction of them. With a seed, its decisions are reproducible.
+    pub fn new(
+        lead_time: Duration,
+        failure_rate: f64,
+        partial_rate: f64,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            lead_time,
+            failure_rate,
+            partial_rate,
+            rng: Mutex::new(match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            }),
+        }
+    }
+}
+
+impl Supplier for MockSupplier {
+    fn place_order(&self, order: &PurchaseOrder) -> Result<Delivery, String> {
+        let mut rng = self.rng.lock().expect("Failed to lock rng");
+        if rng.gen_bool(self.failure_rate) {
+            return Err(String::from("el proveedor no tiene stock"));
+        }
+        let quantity = if rng.gen_bool(self.partial_rate) {
+            let fill = rng.gen_range(MOCK_SUPPLIER_MIN_PARTIAL_FILL..1.0);
+            (order.get_quantity() as f64 * fill) as u64
+        } else {
+            order.get_quantity()
+        };
+        Ok(Delivery::new(
+            order.get_placed_at() + self.lead_time,
+            order.get_container(),
+            quantity,
+        )
+        .for_purchase_order(order.get_id()))
+    }
+
+    fn lead_time(&self) -> Duration {
+        self.lead_time
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Parameters of the mock supplier, taken from the command line.
+pub struct MockSupplierSpec {
+    pub lead_time: Duration,
+    pub failure_rate: f64,
+    pub partial_rate: f64,
+    pub seed: Option<u64>,
+}
+
+impl MockSupplierSpec {
+    /// Creates the supplier described by these parameters.
+    pub fn build(&self) -> Box<dyn Supplier> {
+        Box::new(MockSupplier::new(
+            self.lead_time,
+            self.failure_rate,
+            self.partial_rate,
+            self.seed,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(quantity: u64) -> PurchaseOrder {
+        PurchaseOrder::new(1, ContainerKind::ColdMilk, quantity, Duration::from_secs(2))
+    }
+
+    #[test]
+    fn the_supplier_delivers_the_whole_order_after_its_lead_time() {
+        let supplier = MockSupplier::new(Duration::from_secs(3), 0.0, 0.0, Some(7));
+
+        let delivery = supplier.place_order(&order(1000)).expect("Failed to order");
+
+        assert_eq!(delivery.get_amount(), 1000);
+        assert_eq!(delivery.get_at(), Duration::from_secs(5));
+        assert_eq!(delivery.get_container(), ContainerKind::ColdMilk);
+        assert_eq!(delivery.get_purchase_order(), Some(1));
+    }
+
+    #[test]
+    fn a_partial_fill_delivers_at_least_the_minimum_fraction_of_the_order() {
+        let supplier = MockSupplier::new(Duration::ZERO, 0.0, 1.0, Some(7));
+
+        for _ in 0..100 {
+            let amount = supplier
+                .place_order(&order(1000))
+                .expect("Failed to order")
+                .get_amount();
+            assert!(amount < 1000);
+            assert!(amount >= (1000.0 * MOCK_SUPPLIER_MIN_PARTIAL_FILL) as u64);
+        }
+    }
+
+    #[test]
+    fn a_supplier_that_always_fails_rejects_every_order() {
+        let supplier = MockSupplier::new(Duration::ZERO, 1.0, 0.0, None);
+
+        assert!(supplier.place_order(&order(1000)).is_err());
+    }
+}
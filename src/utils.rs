@@ -1,6 +1,9 @@
 use crate::Order;
+use serde::Deserialize;
+use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 /// Represents the amount of resources in the coffee machine.
 pub enum Resource {
     Coffee = 0,
@@ -8,9 +11,53 @@ pub enum Resource {
     Water,
 }
 
+impl Resource {
+    /// Every ingredient, in the usual order they are applied.
+    pub const ALL: [Resource; 3] = [Resource::Coffee, Resource::Milk, Resource::Water];
+
+    /// Returns the name used to refer to the ingredient in the input files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resource::Coffee => "coffee",
+            Resource::Milk => "milk",
+            Resource::Water => "water",
+        }
+    }
+
+    /// Returns the name of the ingredient used in the messages.
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            Resource::Coffee => "café",
+            Resource::Milk => "leche",
+            Resource::Water => "agua",
+        }
+    }
+}
+
+impl FromStr for Resource {
+    type Err = String;
+
+    /// Accepts the name used in the input files or in the messages.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Resource::ALL
+            .into_iter()
+            .find(|ingredient| ingredient.name() == name || ingredient.field_name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Ingrediente desconocido: {} (opciones: coffee, milk, water)",
+                    s
+                )
+            })
+    }
+}
+
 /// Useful for the coffee machine to know if an order has arrived or if it should shutdown.
 pub enum Message {
     Job(Order),
+    /// Asks the dispenser with the given number to leave the pool once it finishes its current
+    /// drink.
+    Retire(u64),
     Shutdown,
 }
 
@@ -35,18 +82,21 @@ pub mod converter {
         to_container.add(value_to_refill);
     }
 
-    /// Refills the given coffee container with the given amount from coffee beans container.
+    /// Refills the given coffee container with the given amount from coffee beans container,
+    /// or with the beans left if they are less.
     pub fn refill_coffee(
         ground_coffee_beans_container: &mut MutexGuard<Container>,
         value_to_refill: &u64,
-        mut coffee_beans_to_grind_container: MutexGuard<Container>,
+        coffee_beans_to_grind_container: &mut MutexGuard<Container>,
     ) {
+        let value_to_refill =
+            &(*value_to_refill).min(*coffee_beans_to_grind_container.get_current_amount());
         println!(
             "{}[Refill de café]{} - Convirtiendo {} de granos para moler a granos molidos",
             COLOR_CYAN, COLOR_RESET, value_to_refill
         );
         refill_container(
-            &mut coffee_beans_to_grind_container,
+            coffee_beans_to_grind_container,
             value_to_refill,
             ground_coffee_beans_container,
         );
@@ -56,21 +106,19 @@ pub mod converter {
         );
     }
 
-    /// Refills the given milk container with the given amount from cold milk container.
+    /// Refills the given milk container with the given amount from cold milk container, or
+    /// with the milk left if it is less.
     pub fn refill_milk(
         milk_foam_container: &mut MutexGuard<Container>,
         value_to_refill: &u64,
-        mut cold_milk_container: MutexGuard<Container>,
+        cold_milk_container: &mut MutexGuard<Container>,
     ) {
+        let value_to_refill = &(*value_to_refill).min(*cold_milk_container.get_current_amount());
         println!(
             "{}[Refill de leche espumada]{} - Convirtiendo {} de leche a leche espumada",
             COLOR_MAGENTA, COLOR_RESET, value_to_refill
         );
-        refill_container(
-            &mut cold_milk_container,
-            value_to_refill,
-            milk_foam_container,
-        );
+        refill_container(cold_milk_container, value_to_refill, milk_foam_container);
         println!(
             "{}[Refill de leche espumada]{} - Terminó de convertir leche espumada",
             COLOR_MAGENTA, COLOR_RESET
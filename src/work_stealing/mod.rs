@@ -0,0 +1,350 @@
+use crate::dispatcher::Dispatcher;
+use crate::dispenser::{Capabilities, Dispenser};
+use crate::order::Order;
+use crate::scheduling_policy::{retire_position, RefillStatus, SchedulingPolicy};
+use crate::utils::Message;
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+
+#[derive(Default)]
+/// Whether a dispenser is looking for work, and whether it was woken up since it started looking.
+struct Idleness {
+    idle: bool,
+    woken: bool,
+}
+
+/// Queue owned by a single dispenser.
+struct Worker {
+    id: u64,
+    capabilities: Capabilities,
+    dequeue: Mutex<VecDeque<Message>>,
+    idleness: Mutex<Idleness>,
+    cvar: Condvar,
+}
+
+impl Worker {
+    /// Marks the dispenser as idle before it checks every queue, so that whatever arrives from
+    /// then on wakes it up
+    fn start_looking(&self) {
+        let mut idleness = self.idleness.lock().expect("Failed to lock idleness");
+        idleness.idle = true;
+        idleness.woken = false;
+    }
+
+    /// Marks the dispenser as busy, once it found a message
+    fn stop_looking(&self) {
+        self.idleness.lock().expect("Failed to lock idleness").idle = false;
+    }
+
+    /// Waits until the dispenser is woken up, if it was not already since it started looking
+    fn wait(&self) {
+        let idleness = self.idleness.lock().expect("Failed to lock idleness");
+        let _idleness = self
+            .cvar
+            .wait_while(idleness, |idleness| !idleness.woken)
+            .expect("Failed to wait for idleness");
+    }
+
+    /// Wakes up the dispenser if it is idle. A busy one checks the queues once it finishes
+    /// Returns True if it was idle
+    fn wake(&self) -> bool {
+        let mut idleness = self.idleness.lock().expect("Failed to lock idleness");
+        if !idleness.idle {
+            return false;
+        }
+        idleness.woken = true;
+        self.cvar.notify_one();
+        true
+    }
+}
+
+/// Dispatcher where every dispenser has its own queue.
+///
+/// Orders are assigned to the least loaded dispenser able to make them, and only one dispenser
+/// is woken up (`notify_one` semantics): that one if it is idle, or otherwise an idle one able to
+/// make the order, which steals it. A dispenser whose queue has nothing for it steals from the
+/// queues of the others, so no order is left behind while there are idle dispensers.
+/// Idle dispensers wait without timeout until they are woken up.
+///
+/// Retire and shutdown messages, as well as the orders no dispenser is able to make, go to a
+/// shared queue that every dispenser checks. Orders are never assigned to a dispenser that is
+/// not able to make them.
+pub struct WorkStealingQueue {
+    workers: RwLock<Vec<Arc<Worker>>>,
+    shared: Mutex<VecDeque<Message>>,
+}
+
+impl WorkStealingQueue {
+    /// Create a dispatcher without dispensers
+    pub fn new() -> Self {
+        Self {
+            workers: RwLock::new(Vec::new()),
+            shared: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Returns the queue of the given dispenser, if it is registered
+    fn worker(&self, id: u64) -> Option<Arc<Worker>> {
+        self.workers
+            .read()
+            .expect("Failed to read workers")
+            .iter()
+            .find(|worker| worker.id == id)
+            .cloned()
+    }
+
+    /// Pushes the order to the least loaded dispenser able to make it, waking it up if it is
+    /// idle, or else an idle dispenser able to steal the order
+    /// Returns the order back if no dispenser is able to make it
+    fn assign(&self, order: Order) -> Option<Order> {
+        let workers = self.workers.read().expect("Failed to read workers");
+        let capable: Vec<&Arc<Worker>> = workers
+            .iter()
+            .filter(|worker| worker.capabilities.can_make(&order))
+            .collect();
+        let Some(worker) = capable
+            .iter()
+            .min_by_key(|worker| worker.dequeue.lock().expect("Failed to lock dequeue").len())
+        else {
+            return Some(order);
+        };
+        worker
+            .dequeue
+            .lock()
+            .expect("Failed to lock dequeue")
+            .push_back(Message::Job(order));
+        if !worker.wake() {
+            capable
+                .iter()
+                .filter(|thief| thief.id != worker.id)
+                .any(|thief| thief.wake());
+        }
+        None
+    }
+
+    /// Wakes up every idle dispenser, used for the messages in the shared queue
+    fn wake_idle_workers(&self) {
+        for worker in self.workers.read().expect("Failed to read workers").iter() {
+            worker.wake();
+        }
+    }
+
+    /// Takes the message chosen by `select` from the shared queue
+    fn take_shared(
+        &self,
+        select: impl FnOnce(&VecDeque<Message>) -> Option<usize>,
+    ) -> Option<Message> {
+        let mut shared = self.shared.lock().expect("Failed to lock shared");
+        let index = select(&shared)?;
+        shared.remove(index)
+    }
+
+    /// Takes from the queues of the other dispensers an order the thief is able to make
+    fn steal(
+        &self,
+        thief: &Dispenser,
+        policy: &dyn SchedulingPolicy,
+        refill_status: &RefillStatus,
+    ) -> Option<Message> {
+        let workers = self.workers.read().expect("Failed to read workers");
+        workers
+            .iter()
+            .filter(|worker| worker.id != thief.get_id())
+            .find_map(|worker| {
+                let mut dequeue = worker.dequeue.lock().expect("Failed to lock dequeue");
+                let index = policy.select_job(&dequeue, refill_status, thief.get_capabilities())?;
+                dequeue.remove(index)
+            })
+    }
+}
+
+impl Dispatcher for WorkStealingQueue {
+    fn register(&self, dispenser: &Dispenser) {
+        self.workers
+            .write()
+            .expect("Failed to write workers")
+            .push(Arc::new(Worker {
+                id: dispenser.get_id(),
+                capabilities: *dispenser.get_capabilities(),
+                dequeue: Mutex::new(VecDeque::new()),
+                idleness: Mutex::new(Idleness::default()),
+                cvar: Condvar::new(),
+            }));
+    }
+
+    fn unregister(&self, dispenser: &Dispenser) {
+        let worker = {
+            let mut workers = self.workers.write().expect("Failed to write workers");
+            match workers
+                .iter()
+                .position(|worker| worker.id == dispenser.get_id())
+            {
+                Some(index) => workers.remove(index),
+                None => return,
+            }
+        };
+        let pending: Vec<Message> = worker
+            .dequeue
+            .lock()
+            .expect("Failed to lock dequeue")
+            .drain(..)
+            .collect();
+        for message in pending {
+            self.push(message);
+        }
+    }
+
+    fn push(&self, message: Message) {
+        let message = match message {
+            Message::Job(order) => match self.assign(order) {
+                Some(order) => Message::Job(order),
+                None => return,
+            },
+            message => message,
+        };
+        self.shared
+            .lock()
+            .expect("Failed to lock shared")
+            .push_back(message);
+        self.wake_idle_workers();
+    }
+
+    fn pop(
+        &self,
+        dispenser: &Dispenser,
+        policy: &dyn SchedulingPolicy,
+        refill_status: &RefillStatus,
+    ) -> Message {
+        let capabilities = dispenser.get_capabilities();
+        let own = self
+            .worker(dispenser.get_id())
+            .expect("Failed to find the queue of the dispenser");
+        loop {
+            own.start_looking();
+            let message = self
+                .take_shared(|shared| retire_position(shared, dispenser))
+                .or_else(|| {
+                    let mut dequeue = own.dequeue.lock().expect("Failed to lock dequeue");
+                    let index = policy.select_job(&dequeue, refill_status, capabilities)?;
+                    dequeue.remove(index)
+                })
+                .or_else(|| self.steal(dispenser, policy, refill_status))
+                .or_else(|| {
+                    self.take_shared(|shared| policy.select(shared, refill_status, dispenser))
+                });
+            if let Some(message) = message {
+                own.stop_looking();
+                return message;
+            }
+            // No hay nada para este dispenser: espera a que le asignen un pedido o a que haya
+            // uno para robar. Si llegó algo mientras revisaba las colas, no espera.
+            own.wait();
+        }
+    }
+
+    fn waiting_orders(&self) -> Vec<Order> {
+        let job = |message: &Message| match message {
+            Message::Job(order) if !order.is_expired() => Some(order.clone()),
+            _ => None,
+        };
+        let mut orders: Vec<Order> = self
+            .shared
+            .lock()
+            .expect("Failed to lock shared")
+            .iter()
+            .filter_map(job)
+            .collect();
+        for worker in self.workers.read().expect("Failed to read workers").iter() {
+            let dequeue = worker.dequeue.lock().expect("Failed to lock dequeue");
+            orders.extend(dequeue.iter().filter_map(job));
+        }
+        orders
+    }
+
+    fn drain(&self) -> Vec<Message> {
+        let mut messages: Vec<Message> = self
+            .shared
+            .lock()
+            .expect("Failed to lock shared")
+            .drain(..)
+            .collect();
+        for worker in self.workers.read().expect("Failed to read workers").iter() {
+            let mut dequeue = worker.dequeue.lock().expect("Failed to lock dequeue");
+            messages.extend(dequeue.drain(..));
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::dispenser::DispenserSpec;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn dispenser(id: u64, capabilities: &str) -> Dispenser {
+        Dispenser::new(
+            id,
+            DispenserSpec::new(
+                capabilities.parse().expect("Failed to parse capabilities"),
+                1.0,
+            ),
+        )
+    }
+
+    #[test]
+    fn an_order_is_never_assigned_to_a_dispenser_unable_to_make_it() {
+        let queue = WorkStealingQueue::new();
+        queue.register(&dispenser(1, "water-only"));
+
+        queue.push(Message::Job(Order::new(0, 3, 1, None)));
+
+        let water_only = queue.worker(1).expect("Failed to find the queue");
+        assert!(water_only.dequeue.lock().unwrap().is_empty());
+        assert_eq!(queue.waiting_orders().len(), 1);
+    }
+
+    #[test]
+    fn expired_orders_are_not_counted_as_waiting() {
+        let queue = WorkStealingQueue::new();
+        queue.register(&dispenser(1, "all"));
+
+        queue.push(Message::Job(Order::new(1, 1, 1, Some(Duration::ZERO))));
+        queue.push(Message::Job(Order::new(1, 1, 1, None)));
+        thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(queue.waiting_orders().len(), 1);
+        assert_eq!(queue.drain().len(), 2);
+    }
+
+    #[test]
+    fn an_idle_dispenser_is_woken_up_to_steal_an_order_assigned_to_a_busy_one() {
+        let queue = Arc::new(WorkStealingQueue::new());
+        let busy = dispenser(1, "all");
+        let idle = dispenser(2, "all");
+        queue.register(&busy);
+        queue.register(&idle);
+        let (sender, receiver) = mpsc::channel();
+        let thief = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let policy = Config::default().policy.build();
+                let message = queue.pop(&idle, policy.as_ref(), &RefillStatus::new());
+                sender.send(matches!(message, Message::Job(_))).unwrap();
+            })
+        };
+        while !queue.worker(2).unwrap().idleness.lock().unwrap().idle {
+            thread::yield_now();
+        }
+
+        // Ambas colas están vacías, así que el pedido se asigna al primer dispenser, que no
+        // está esperando pedidos.
+        queue.push(Message::Job(Order::new(1, 1, 1, None)));
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)), Ok(true));
+        thief.join().unwrap();
+    }
+}